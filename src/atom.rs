@@ -0,0 +1,325 @@
+// Copyright 2014 The SAX-RS Developers. For a full listing of the authors,
+// refer to the AUTHORS file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Interned names, stored in a libxml2 dictionary
+//!
+//! A document usually uses only a few dozen distinct names for its elements
+//! and attributes. With `intern_names` enabled, the parser hands these out as
+//! `Atom`s from the dictionary it already keeps them in, so each name is
+//! stored once, however often it occurs, and atoms are compared by pointer
+//! rather than by content.
+//!
+//! # Example
+//!
+//! ~~~rust
+//! use std::collections::HashMap;
+//!
+//! struct Counter { counts: HashMap<sax::Atom, uint> }
+//!
+//! impl sax::SaxHandler for Counter {
+//!     fn start_element_atom(&mut self, name: &sax::AtomQName, _: &sax::Attributes,
+//!                           _: &[sax::Namespace]) {
+//!         self.counts.insert_or_update_with(name.local_name.clone(), 1, |_, n| *n += 1);
+//!     }
+//! }
+//!
+//! let mut counter = Counter { counts: HashMap::new() };
+//! sax::ParserConfig::new().intern_names(true).parse_with_handler(XML_DATA, &mut counter);
+//! ~~~
+
+use libc::c_int;
+use std::fmt;
+use std::hash;
+use std::kinds::marker;
+
+use super::{ffi, init_parser, str_from_buf};
+
+/// A table of interned names, backed by a libxml2 dictionary. Cloning the
+/// table shares it rather than copying it.
+///
+/// The table can only be used on the task that created it, but the atoms
+/// that it hands out can be sent anywhere.
+pub struct Names {
+    dict: ffi::xmlDictPtr,
+    no_send: marker::NoSend,
+}
+
+impl Names {
+    /// Creates an empty table.
+    pub fn new() -> Names {
+        init_parser();
+        let dict = unsafe { ffi::xmlDictCreate() };
+        if dict.is_null() {
+            fail!("could not allocate a dictionary");
+        }
+        Names { dict: dict, no_send: marker::NoSend }
+    }
+
+    /// Shares a dictionary that libxml2 already holds, such as the one that
+    /// a parser interns names in.
+    pub unsafe fn from_dict(dict: ffi::xmlDictPtr) -> Names {
+        ffi::xmlDictReference(dict);
+        Names { dict: dict, no_send: marker::NoSend }
+    }
+
+    /// Interns `name`, adding it to the table if it is not there already.
+    pub fn atom(&self, name: &str) -> Atom {
+        unsafe {
+            let ptr = ffi::xmlDictLookup(self.dict, name.as_ptr(), name.len() as c_int);
+            if ptr.is_null() {
+                fail!("could not intern `{}`", name);
+            }
+            Atom::new(self.dict, ptr)
+        }
+    }
+
+    /// Finds the atom for `name`, if it has already been interned.
+    pub fn find(&self, name: &str) -> Option<Atom> {
+        unsafe {
+            let ptr = ffi::xmlDictExists(self.dict, name.as_ptr(), name.len() as c_int);
+            if ptr.is_null() { None } else { Some(Atom::new(self.dict, ptr)) }
+        }
+    }
+
+    /// The number of distinct names in the table.
+    pub fn len(&self) -> uint {
+        unsafe { ffi::xmlDictSize(self.dict) as uint }
+    }
+}
+
+impl Clone for Names {
+    fn clone(&self) -> Names {
+        unsafe { ffi::xmlDictReference(self.dict) };
+        Names { dict: self.dict, no_send: marker::NoSend }
+    }
+}
+
+impl Drop for Names {
+    fn drop(&mut self) {
+        unsafe { ffi::xmlDictFree(self.dict) };
+    }
+}
+
+/// A name interned in a `Names` table. Every atom for the same name from
+/// the same table points to the same string, so comparing them is cheap.
+/// Each atom keeps its table alive.
+pub struct Atom {
+    dict: ffi::xmlDictPtr,
+    /// The null-terminated string in the table
+    name: *const ffi::xmlChar,
+}
+
+impl Atom {
+    /// Takes a reference to the dictionary that holds `name`.
+    unsafe fn new(dict: ffi::xmlDictPtr, name: *const ffi::xmlChar) -> Atom {
+        ffi::xmlDictReference(dict);
+        Atom { dict: dict, name: name }
+    }
+
+    /// Wraps a null-terminated name that libxml2 passed to a callback. The
+    /// parser hands out most names straight from `dict`, in which case the
+    /// pointer is used as it is, and only the others are looked up.
+    pub unsafe fn from_dict_ptr(dict: ffi::xmlDictPtr, name: *const ffi::xmlChar) -> Atom {
+        if ffi::xmlDictOwns(dict, name) == 1 {
+            Atom::new(dict, name)
+        } else {
+            Atom::interned(dict, ffi::xmlDictLookup(dict, name, -1))
+        }
+    }
+
+    /// Interns the qualified name `prefix:name` in `dict`, or just `name` if
+    /// `prefix` is null. Both must be null-terminated.
+    pub unsafe fn from_qname_buf(dict: ffi::xmlDictPtr, prefix: *const ffi::xmlChar,
+                                 name: *const ffi::xmlChar) -> Atom {
+        if prefix.is_null() { return Atom::from_dict_ptr(dict, name) }
+        Atom::interned(dict, ffi::xmlDictQLookup(dict, prefix, name))
+    }
+
+    unsafe fn interned(dict: ffi::xmlDictPtr, ptr: *const ffi::xmlChar) -> Atom {
+        if ptr.is_null() {
+            fail!("could not intern a name");
+        }
+        Atom::new(dict, ptr)
+    }
+}
+
+impl Str for Atom {
+    fn as_slice<'a>(&'a self) -> &'a str {
+        unsafe { str_from_buf(self.name) }
+    }
+}
+
+impl Clone for Atom {
+    fn clone(&self) -> Atom {
+        unsafe { Atom::new(self.dict, self.name) }
+    }
+}
+
+impl Drop for Atom {
+    fn drop(&mut self) {
+        // the dictionary's reference count is guarded by libxml2, so this is
+        // safe on any task
+        unsafe { ffi::xmlDictFree(self.dict) };
+    }
+}
+
+impl PartialEq for Atom {
+    /// Atoms from the same table are equal only if they are the same string.
+    /// Atoms from different tables have to be compared by content.
+    fn eq(&self, other: &Atom) -> bool {
+        if self.dict == other.dict {
+            self.name == other.name
+        } else {
+            self.as_slice() == other.as_slice()
+        }
+    }
+}
+
+impl Eq for Atom {}
+
+impl<S: hash::Writer> hash::Hash<S> for Atom {
+    /// Atoms are hashed by content, so that ones from different tables still
+    /// hash alike when they are equal.
+    fn hash(&self, state: &mut S) {
+        self.as_slice().hash(state)
+    }
+}
+
+impl fmt::Show for Atom {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "{}", self.as_slice())
+    }
+}
+
+/// A namespace-qualified name made of atoms. Without namespace processing
+/// the local name is the whole name as it appears in the document.
+#[deriving(PartialEq, Eq, Clone, Hash)]
+pub struct AtomQName {
+    /// The name without its prefix, eg. `a` in `test:a`.
+    pub local_name: Atom,
+    /// The prefix used in the document, if any.
+    pub prefix: Option<Atom>,
+    /// The URI of the namespace that the prefix resolved to, if any.
+    pub namespace_uri: Option<Atom>,
+}
+
+impl AtomQName {
+    /// Interns the strings passed to one of libxml2's element callbacks in
+    /// `dict`. Any of them but `localname` may be null.
+    pub unsafe fn from_buf(dict: ffi::xmlDictPtr,
+                           localname: *const ffi::xmlChar,
+                           prefix: *const ffi::xmlChar,
+                           uri: *const ffi::xmlChar) -> AtomQName {
+        let atom = |ptr: *const ffi::xmlChar| {
+            if ptr.is_null() { None } else { Some(Atom::from_dict_ptr(dict, ptr)) }
+        };
+        AtomQName {
+            local_name:     Atom::from_dict_ptr(dict, localname),
+            prefix:         atom(prefix),
+            namespace_uri:  atom(uri),
+        }
+    }
+}
+
+impl fmt::Show for AtomQName {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self.prefix {
+            Some(ref prefix) => write!(fmt, "{}:{}", *prefix, self.local_name),
+            None => write!(fmt, "{}", self.local_name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Atom, AtomQName, Names};
+    use super::super::{ParserConfig, ParseEvent, SaxHandler, Attributes, Namespace};
+    use super::super::StartElementAtom;
+
+    #[test]
+    fn test_names() {
+        let names = Names::new();
+        let a = names.atom("a");
+        assert_eq!(a.as_slice(), "a");
+        assert_eq!(names.atom("a"), a);
+        assert!(names.atom("b") != a);
+        assert_eq!(names.find("a"), Some(a.clone()));
+        assert_eq!(names.find("c"), None);
+        assert_eq!(names.len(), 2);
+
+        // atoms outlive their table, and compare by content across tables
+        let other = Names::new().atom("a");
+        drop(names);
+        assert_eq!(other, a);
+    }
+
+    #[test]
+    fn test_intern_names() {
+        struct Interner { names: Option<Names>, elements: Vec<Atom>, atts: Vec<Atom> }
+
+        impl SaxHandler for Interner {
+            fn names(&mut self, names: &Names) {
+                self.names = Some(names.clone());
+            }
+
+            fn start_element_atom(&mut self, name: &AtomQName, atts: &Attributes, _: &[Namespace]) {
+                self.elements.push(name.local_name.clone());
+                for att in atts.iter() {
+                    self.atts.push(att.atom().unwrap().clone());
+                }
+            }
+        }
+
+        // every occurrence of a name is the same string in the parser's table
+        let src = "<a><b id=\"1\"/><b id=\"2\"/><a id=\"3\"/></a>";
+        let mut handler = Interner { names: None, elements: Vec::new(), atts: Vec::new() };
+        ParserConfig::new().intern_names(true).parse_with_handler(src, &mut handler);
+        let names = handler.names.take().unwrap();
+        let (a, b) = (names.find("a").unwrap(), names.find("b").unwrap());
+        let id = names.find("id").unwrap();
+        assert_eq!(handler.elements, vec![a.clone(), b.clone(), b, a]);
+        assert_eq!(handler.atts, vec![id.clone(), id.clone(), id]);
+
+        let mut handler = Interner { names: None, elements: Vec::new(), atts: Vec::new() };
+        ParserConfig::new().parse_with_handler("<a/>", &mut handler);
+        assert!(handler.names.is_none());
+        assert!(handler.elements.is_empty());
+    }
+
+    #[test]
+    fn test_atom_events() {
+        let src = "<x:a xmlns:x=\"urn:x\" x:id=\"1\"><b/></x:a>";
+        let config = ParserConfig::new().namespaces(true);
+        let events: Vec<ParseEvent> = config.clone().intern_names(true).parse_str(src).iter()
+            .map(|result| result.unwrap())
+            .collect();
+        match events[1] {
+            StartElementAtom(ref name, ref atts, ref namespaces) => {
+                assert_eq!(name.local_name.as_slice(), "a");
+                assert_eq!(name.namespace_uri.as_ref().map(|uri| uri.as_slice()), Some("urn:x"));
+                assert_eq!(atts[0].atom().map(|atom| atom.as_slice()), Some("x:id"));
+                assert_eq!(namespaces.len(), 1);
+            }
+            ref event => fail!("unexpected event {}", event),
+        }
+
+        // the events read the same as the ones with plain names
+        let expected: Vec<String> = config.parse_str(src).iter()
+            .map(|result| result.unwrap().to_string())
+            .collect();
+        let actual: Vec<String> = events.iter().map(|event| event.to_string()).collect();
+        assert_eq!(actual, expected);
+    }
+}
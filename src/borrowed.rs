@@ -0,0 +1,474 @@
+// Copyright 2014 The SAX-RS Developers. For a full listing of the authors,
+// refer to the AUTHORS file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Parse events that borrow their data from the parser
+//!
+//! A `SaxHandler` that overrides `event` receives each event as a
+//! `ParseEventRef`, whose strings point straight into libxml2's buffers, so
+//! nothing is allocated to produce it. The data only lives for as long as the
+//! call, and anything that has to be kept must be copied out, either a piece
+//! at a time or with `into_owned`.
+//!
+//! The one exception is `Dtd`. Declarations such as content models are
+//! rebuilt from libxml2's own structures, so each `DtdEvent` is allocated
+//! before it is lent out. They only come from the document type
+//! declaration, ahead of the content.
+//!
+//! # Example
+//!
+//! ~~~rust
+//! use sax::borrowed;
+//!
+//! struct LinkCounter { count: uint }
+//!
+//! impl sax::SaxHandler for LinkCounter {
+//!     fn event(&mut self, event: borrowed::ParseEventRef) {
+//!         match event {
+//!             borrowed::StartElement("a", atts) if atts.find("href").is_some() => {
+//!                 self.count += 1;
+//!             }
+//!             _ => {}
+//!         }
+//!     }
+//! }
+//!
+//! let mut counter = LinkCounter { count: 0 };
+//! sax::parse_with_handler(HTML_DATA, &mut counter);
+//! ~~~
+
+use libc::c_int;
+use std::fmt;
+use std::ptr::null;
+use std::slice;
+
+use super::{ffi, str_from_buf, str_from_buf_len, str_from_buf_opt, slice_from_buf};
+use super::{ParseEvent, QName, Namespace, Attribute, Attributes, OwnedName, SharedName};
+use super::{Atom, AtomQName};
+use super::dtd::DtdEvent;
+
+/// A parse event whose data is borrowed from the parser. Each variant is the
+/// counterpart of the `ParseEvent` variant with the same name.
+#[deriving(Clone)]
+pub enum ParseEventRef<'a> {
+    /// The document has begun to be processed.
+    StartDocument,
+    /// The document processing has finished.
+    EndDocument,
+    /// An opening tag was parsed.
+    StartElement(&'a str, AttributesRef<'a>),
+    /// A closing tag was parsed.
+    EndElement(&'a str),
+    /// An opening tag was parsed with namespace processing enabled.
+    StartElementNs(QNameRef<'a>, AttributesRef<'a>, NamespacesRef<'a>),
+    /// A closing tag was parsed with namespace processing enabled.
+    EndElementNs(QNameRef<'a>),
+    /// An opening tag was parsed with `intern_names` enabled.
+    StartElementAtom(AtomQName, AttributesRef<'a>, NamespacesRef<'a>),
+    /// A closing tag was parsed with `intern_names` enabled.
+    EndElementAtom(AtomQName),
+    /// Some characters between tags have been received.
+    Characters(&'a str),
+    /// A reference to a general entity that was left unexpanded.
+    EntityReference(&'a str),
+    /// Whitespace that is not significant to the document.
+    IgnorableWhitespace(&'a str),
+    /// A comment tag was parsed.
+    Comment(&'a str),
+    /// A `CDATA` block was parsed.
+    CdataBlock(&'a str),
+    /// A processing instruction was parsed, with its target and data.
+    ProcessingInstruction(&'a str, Option<&'a str>),
+    /// Part of the document type declaration was parsed. Unlike the other
+    /// events, this is built as an owned `DtdEvent` first.
+    Dtd(&'a DtdEvent),
+}
+
+impl<'a> ParseEventRef<'a> {
+    /// Copies the event out of the parser's buffers.
+    pub fn into_owned(self) -> ParseEvent {
+        match self {
+            StartDocument => super::StartDocument,
+            EndDocument => super::EndDocument,
+            StartElement(name, atts) => super::StartElement(name.to_string(), atts.into_owned()),
+            EndElement(name) => super::EndElement(name.to_string()),
+            StartElementNs(name, atts, namespaces) => {
+                super::StartElementNs(name.into_owned(), atts.into_owned(), namespaces.into_owned())
+            }
+            EndElementNs(name) => super::EndElementNs(name.into_owned()),
+            StartElementAtom(name, atts, namespaces) => {
+                super::StartElementAtom(name, atts.into_owned(), namespaces.into_owned())
+            }
+            EndElementAtom(name) => super::EndElementAtom(name),
+            Characters(ch) => super::Characters(ch.to_string()),
+            EntityReference(name) => super::EntityReference(name.to_string()),
+            IgnorableWhitespace(ch) => super::IgnorableWhitespace(ch.to_string()),
+            Comment(value) => super::Comment(value.to_string()),
+            CdataBlock(value) => super::CdataBlock(value.to_string()),
+            ProcessingInstruction(target, data) => {
+                super::ProcessingInstruction(target.to_string(), data.map(|d| d.to_string()))
+            }
+            Dtd(event) => super::Dtd(event.clone()),
+        }
+    }
+}
+
+impl<'a> fmt::Show for ParseEventRef<'a> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "{}", self.clone().into_owned())
+    }
+}
+
+/// A namespace-qualified name, borrowed from the parser.
+#[deriving(PartialEq, Eq, Clone)]
+pub struct QNameRef<'a> {
+    /// The name without its prefix, eg. `a` in `test:a`.
+    pub local_name: &'a str,
+    /// The prefix used in the document, if any.
+    pub prefix: Option<&'a str>,
+    /// The URI of the namespace that the prefix resolved to, if any.
+    pub namespace_uri: Option<&'a str>,
+}
+
+impl<'a> QNameRef<'a> {
+    /// Borrows a name from the strings passed to libxml2's SAX2 callbacks,
+    /// which must outlive `'a`.
+    pub unsafe fn from_buf(localname: *const ffi::xmlChar,
+                           prefix: *const ffi::xmlChar,
+                           uri: *const ffi::xmlChar) -> QNameRef<'a> {
+        QNameRef {
+            local_name:     str_from_buf(localname),
+            prefix:         str_from_buf_opt(prefix),
+            namespace_uri:  str_from_buf_opt(uri),
+        }
+    }
+
+    /// Copies the name out of the parser's buffers.
+    pub fn into_owned(self) -> QName {
+        QName {
+            local_name:     self.local_name.to_string(),
+            prefix:         self.prefix.map(|p| p.to_string()),
+            namespace_uri:  self.namespace_uri.map(|uri| uri.to_string()),
+        }
+    }
+}
+
+impl<'a> fmt::Show for QNameRef<'a> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self.prefix {
+            Some(prefix) => write!(fmt, "{}:{}", prefix, self.local_name),
+            None => write!(fmt, "{}", self.local_name),
+        }
+    }
+}
+
+/// A namespace declaration, borrowed from the parser.
+#[deriving(PartialEq, Eq, Clone)]
+pub struct NamespaceRef<'a> {
+    /// The declared prefix, or `None` for a default namespace declaration.
+    pub prefix: Option<&'a str>,
+    /// The namespace URI.
+    pub uri: &'a str,
+}
+
+impl<'a> NamespaceRef<'a> {
+    /// Copies the declaration out of the parser's buffers.
+    pub fn into_owned(self) -> Namespace {
+        Namespace {
+            prefix: self.prefix.map(|p| p.to_string()),
+            uri:    self.uri.to_string(),
+        }
+    }
+}
+
+/// The namespaces declared on an element, borrowed from the parser.
+#[deriving(Clone)]
+pub struct NamespacesRef<'a> {
+    /// A prefix and URI for each declaration
+    ptrs: &'a [*const ffi::xmlChar],
+}
+
+impl<'a> NamespacesRef<'a> {
+    /// Borrows the declarations passed to libxml2's `startElementNs`
+    /// callback, which must outlive `'a`.
+    pub unsafe fn from_buf(nb_namespaces: c_int,
+                           namespaces: *const *const ffi::xmlChar) -> NamespacesRef<'a> {
+        NamespacesRef { ptrs: slice_from_buf(namespaces, nb_namespaces as uint * 2) }
+    }
+
+    /// The number of declarations.
+    pub fn len(&self) -> uint {
+        self.ptrs.len() / 2
+    }
+
+    /// Whether the element declares no namespaces.
+    pub fn is_empty(&self) -> bool {
+        self.ptrs.is_empty()
+    }
+
+    /// Returns an iterator over the declarations, in document order.
+    pub fn iter(&self) -> NamespaceRefs<'a> {
+        NamespaceRefs { chunks: self.ptrs.chunks(2) }
+    }
+
+    /// Copies the declarations out of the parser's buffers.
+    pub fn into_owned(self) -> Vec<Namespace> {
+        self.iter().map(|ns| ns.into_owned()).collect()
+    }
+}
+
+/// An iterator over borrowed namespace declarations.
+pub struct NamespaceRefs<'a> {
+    chunks: slice::Chunks<'a, *const ffi::xmlChar>,
+}
+
+impl<'a> Iterator<NamespaceRef<'a>> for NamespaceRefs<'a> {
+    fn next(&mut self) -> Option<NamespaceRef<'a>> {
+        self.chunks.next().map(|ptrs| unsafe {
+            NamespaceRef {
+                prefix: str_from_buf_opt(ptrs[0]),
+                uri:    str_from_buf_opt(ptrs[1]).unwrap_or(""),
+            }
+        })
+    }
+}
+
+/// An attribute of an element, borrowed from the parser.
+#[deriving(PartialEq, Eq, Clone)]
+pub struct AttributeRef<'a> {
+    /// The local name when namespaces are enabled, otherwise the qualified
+    /// name as it appears in the document
+    name: &'a str,
+    prefix: Option<&'a str>,
+    value: &'a str,
+    namespace_uri: Option<&'a str>,
+    /// The dictionary to intern the name in, or null
+    dict: ffi::xmlDictPtr,
+}
+
+impl<'a> AttributeRef<'a> {
+    /// Reads a SAX1 name and value pair, or a SAX2 5-tuple of `localname`,
+    /// `prefix`, `URI`, `value` and `end`.
+    unsafe fn from_ptrs(ptrs: &[*const ffi::xmlChar],
+                        dict: ffi::xmlDictPtr) -> AttributeRef<'a> {
+        if ptrs.len() == 2 {
+            return AttributeRef {
+                name:           str_from_buf(ptrs[0]),
+                prefix:         None,
+                // the HTML parser gives boolean attributes no value
+                value:          str_from_buf_opt(ptrs[1]).unwrap_or(""),
+                namespace_uri:  None,
+                dict:           dict,
+            };
+        }
+        AttributeRef {
+            name:           str_from_buf(ptrs[0]),
+            prefix:         str_from_buf_opt(ptrs[1]),
+            value:          str_from_buf_len(ptrs[3], ptrs[4] as uint - ptrs[3] as uint),
+            namespace_uri:  str_from_buf_opt(ptrs[2]),
+            dict:           dict,
+        }
+    }
+
+    /// Whether `name` is the qualified name of the attribute, as it appears
+    /// in the document.
+    pub fn has_name(&self, name: &str) -> bool {
+        match self.prefix {
+            Some(prefix) => {
+                name.len() == prefix.len() + 1 + self.name.len()
+                    && name.starts_with(prefix)
+                    && name.as_bytes()[prefix.len()] == b':'
+                    && name.ends_with(self.name)
+            }
+            None => name == self.name,
+        }
+    }
+
+    /// The name without its prefix, eg. `foo` in `test:foo`.
+    pub fn local_name(&self) -> &'a str {
+        self.name.split(':').last().unwrap()
+    }
+
+    /// The prefix used in the document. This is only available when
+    /// namespace processing is enabled.
+    pub fn prefix(&self) -> Option<&'a str> {
+        self.prefix
+    }
+
+    pub fn value(&self) -> &'a str {
+        self.value
+    }
+
+    /// The URI of the namespace of the attribute. This is only available
+    /// when namespace processing is enabled.
+    pub fn namespace_uri(&self) -> Option<&'a str> {
+        self.namespace_uri
+    }
+
+    /// Interns the qualified name of the attribute. This is only available
+    /// when `intern_names` is enabled.
+    pub fn atom(&self) -> Option<Atom> {
+        if self.dict.is_null() { return None }
+        // the name and prefix still point at libxml2's null-terminated strings
+        let prefix = self.prefix.map_or(null(), |prefix| prefix.as_ptr());
+        Some(unsafe { Atom::from_qname_buf(self.dict, prefix, self.name.as_ptr()) })
+    }
+
+    /// Copies the attribute out of the parser's buffers.
+    pub fn into_owned(self) -> Attribute {
+        let name = match (self.atom(), self.prefix) {
+            (Some(atom), _) => SharedName(atom),
+            (None, Some(prefix)) => OwnedName(format!("{}:{}", prefix, self.name)),
+            (None, None) => OwnedName(self.name.to_string()),
+        };
+        Attribute {
+            name:           name,
+            value:          self.value.to_string(),
+            namespace_uri:  self.namespace_uri.map(|uri| uri.to_string()),
+        }
+    }
+}
+
+impl<'a> fmt::Show for AttributeRef<'a> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self.prefix {
+            Some(prefix) => write!(fmt, "{}:{}=\"{}\"", prefix, self.name, self.value),
+            None => write!(fmt, "{}=\"{}\"", self.name, self.value),
+        }
+    }
+}
+
+/// The attributes of an element, borrowed from the parser.
+#[deriving(Clone)]
+pub struct AttributesRef<'a> {
+    ptrs: &'a [*const ffi::xmlChar],
+    /// The number of pointers that describe each attribute
+    stride: uint,
+    dict: ffi::xmlDictPtr,
+}
+
+impl<'a> AttributesRef<'a> {
+    /// Borrows the null-terminated list of names and values passed to
+    /// libxml2's `startElement` callback, which must outlive `'a`.
+    pub unsafe fn from_buf(atts: *const *const ffi::xmlChar) -> AttributesRef<'a> {
+        let mut len = 0;
+        while !atts.is_null() && !(*atts.offset(len as int)).is_null() {
+            len += 2;
+        }
+        AttributesRef { ptrs: slice_from_buf(atts, len), stride: 2, dict: null() }
+    }
+
+    /// Borrows the attributes passed to libxml2's `startElementNs` callback,
+    /// which must outlive `'a`.
+    pub unsafe fn from_ns_buf(nb_attributes: c_int,
+                              atts: *const *const ffi::xmlChar) -> AttributesRef<'a> {
+        AttributesRef { ptrs: slice_from_buf(atts, nb_attributes as uint * 5), stride: 5,
+                        dict: null() }
+    }
+
+    /// Interns the names of the attributes in `dict` when they are copied out
+    /// or asked for as atoms.
+    pub unsafe fn interned_in(self, dict: ffi::xmlDictPtr) -> AttributesRef<'a> {
+        AttributesRef { dict: dict, ..self }
+    }
+
+    /// The number of attributes.
+    pub fn len(&self) -> uint {
+        self.ptrs.len() / self.stride
+    }
+
+    /// Whether the element has no attributes.
+    pub fn is_empty(&self) -> bool {
+        self.ptrs.is_empty()
+    }
+
+    /// Returns an iterator over the attributes, in document order.
+    pub fn iter(&self) -> AttributeRefs<'a> {
+        AttributeRefs { chunks: self.ptrs.chunks(self.stride), dict: self.dict }
+    }
+
+    /// Finds the value of the attribute with the qualified name `name`.
+    pub fn find(&self, name: &str) -> Option<&'a str> {
+        self.iter().find(|att| att.has_name(name)).map(|att| att.value())
+    }
+
+    /// Finds the value of an attribute by its namespace URI and local name.
+    /// This only finds attributes when namespace processing is enabled.
+    pub fn find_ns(&self, namespace_uri: &str, local_name: &str) -> Option<&'a str> {
+        self.iter()
+            .find(|att| att.namespace_uri() == Some(namespace_uri)
+                        && att.local_name() == local_name)
+            .map(|att| att.value())
+    }
+
+    /// Copies the attributes out of the parser's buffers.
+    pub fn into_owned(self) -> Attributes {
+        Attributes(self.iter().map(|att| att.into_owned()).collect())
+    }
+}
+
+/// An iterator over borrowed attributes.
+pub struct AttributeRefs<'a> {
+    chunks: slice::Chunks<'a, *const ffi::xmlChar>,
+    dict: ffi::xmlDictPtr,
+}
+
+impl<'a> Iterator<AttributeRef<'a>> for AttributeRefs<'a> {
+    fn next(&mut self) -> Option<AttributeRef<'a>> {
+        let dict = self.dict;
+        self.chunks.next().map(|ptrs| unsafe { AttributeRef::from_ptrs(ptrs, dict) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ParseEventRef, StartElementNs, Characters};
+    use super::super::{ParserConfig, SaxHandler};
+
+    #[test]
+    fn test_borrowed_events() {
+        struct Borrower { owned: Vec<String>, text: uint, link: Option<String> }
+
+        impl SaxHandler for Borrower {
+            fn event(&mut self, event: ParseEventRef) {
+                match event {
+                    StartElementNs(ref name, ref atts, ref namespaces) => {
+                        assert_eq!(namespaces.len(), if name.local_name == "a" { 1 } else { 0 });
+                        if name.local_name == "b" {
+                            assert_eq!(atts.len(), 2);
+                            assert_eq!(atts.find("l:href"), Some("x.xml"));
+                            assert_eq!(atts.find("href"), None);
+                            assert_eq!(atts.find_ns("urn:l", "href"), Some("x.xml"));
+                            self.link = atts.iter().next().map(|att| att.to_string());
+                        }
+                    }
+                    Characters(ch) => self.text += ch.len(),
+                    _ => {}
+                }
+                self.owned.push(event.into_owned().to_string());
+            }
+        }
+
+        let src = "<a xmlns:l=\"urn:l\"><b l:href=\"x.xml\" id=\"1\">text</b></a>";
+        let mut handler = Borrower { owned: Vec::new(), text: 0, link: None };
+        ParserConfig::new().namespaces(true).parse_with_handler(src, &mut handler);
+        assert_eq!(handler.text, 4);
+        assert_eq!(handler.link, Some("l:href=\"x.xml\"".to_string()));
+
+        let expected: Vec<String> = ParserConfig::new().namespaces(true).parse_str(src).iter()
+            .map(|result| result.unwrap().to_string())
+            .collect();
+        assert_eq!(handler.owned, expected);
+    }
+}
@@ -0,0 +1,415 @@
+// Copyright 2014 The SAX-RS Developers. For a full listing of the authors,
+// refer to the AUTHORS file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Document type declarations
+
+use libc::c_int;
+use std::fmt;
+use std::string;
+
+use super::ffi;
+use super::from_buf_opt;
+
+/// The public and system identifiers of an external resource.
+#[deriving(PartialEq, Eq, Clone)]
+pub struct ExternalId {
+    pub public_id: Option<String>,
+    pub system_id: Option<String>,
+}
+
+impl ExternalId {
+    pub unsafe fn from_buf(public_id: *const ffi::xmlChar,
+                           system_id: *const ffi::xmlChar) -> ExternalId {
+        ExternalId {
+            public_id: from_buf_opt(public_id),
+            system_id: from_buf_opt(system_id),
+        }
+    }
+}
+
+impl fmt::Show for ExternalId {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match (&self.public_id, &self.system_id) {
+            (&Some(ref public_id), &Some(ref system_id)) => write!(fmt, " PUBLIC \"{}\" \"{}\"", *public_id, *system_id),
+            (&Some(ref public_id), &None) => write!(fmt, " PUBLIC \"{}\"", *public_id),
+            (&None, &Some(ref system_id)) => write!(fmt, " SYSTEM \"{}\"", *system_id),
+            (&None, &None) => Ok(()),
+        }
+    }
+}
+
+/// Events from the document type declaration.
+#[deriving(PartialEq, Eq, Clone)]
+pub enum DtdEvent {
+    /// A `<!DOCTYPE>` declaration was parsed, with the name of the root
+    /// element and the identifiers of the external subset.
+    DoctypeDecl(String, ExternalId),
+    /// The `<!DOCTYPE>` declaration names an external subset. This is sent
+    /// whether or not the parser is set up to load it, and the declarations
+    /// in it only follow when it is.
+    ExternalSubset(String, ExternalId),
+    /// An `<!ELEMENT>` declaration was parsed.
+    ElementDecl(String, ContentSpec),
+    /// An `<!ATTLIST>` declaration was parsed. Each attribute in the list is
+    /// reported separately.
+    AttributeDecl(AttributeDef),
+    /// A parsed `<!ENTITY>` declaration was parsed.
+    EntityDecl(EntityDef),
+    /// A `<!NOTATION>` declaration was parsed.
+    NotationDecl(String, ExternalId),
+    /// An unparsed `<!ENTITY>` declaration was parsed, with the name of its
+    /// notation.
+    UnparsedEntityDecl(String, ExternalId, String),
+}
+
+impl fmt::Show for DtdEvent {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DoctypeDecl(ref name, ref id) => write!(fmt, "<!DOCTYPE {}{}>", *name, *id),
+            ExternalSubset(ref name, ref id) => write!(fmt, "EXTERNAL SUBSET {}{}", *name, *id),
+            ElementDecl(ref name, ref spec) => write!(fmt, "<!ELEMENT {} {}>", *name, *spec),
+            AttributeDecl(ref def) => write!(fmt, "{}", *def),
+            EntityDecl(ref def) => write!(fmt, "{}", *def),
+            NotationDecl(ref name, ref id) => write!(fmt, "<!NOTATION {}{}>", *name, *id),
+            UnparsedEntityDecl(ref name, ref id, ref notation) => {
+                write!(fmt, "<!ENTITY {}{} NDATA {}>", *name, *id, *notation)
+            }
+        }
+    }
+}
+
+/// How often a content particle may occur.
+#[deriving(PartialEq, Eq, Clone)]
+pub enum Occurrence {
+    /// Exactly once
+    Once,
+    /// `?`
+    Optional,
+    /// `*`
+    ZeroOrMore,
+    /// `+`
+    OneOrMore,
+}
+
+impl Occurrence {
+    fn from_constant(value: c_int) -> Occurrence {
+        match value {
+            ffi::XML_ELEMENT_CONTENT_OPT    => Optional,
+            ffi::XML_ELEMENT_CONTENT_MULT   => ZeroOrMore,
+            ffi::XML_ELEMENT_CONTENT_PLUS   => OneOrMore,
+            _                               => Once,
+        }
+    }
+}
+
+impl fmt::Show for Occurrence {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Once => Ok(()),
+            Optional => write!(fmt, "?"),
+            ZeroOrMore => write!(fmt, "*"),
+            OneOrMore => write!(fmt, "+"),
+        }
+    }
+}
+
+/// The content model of an element.
+#[deriving(PartialEq, Eq, Clone)]
+pub enum ContentSpec {
+    /// `EMPTY`
+    EmptyContent,
+    /// `ANY`
+    AnyContent,
+    /// `(#PCDATA | a | b)*`, with the names of the elements that may be mixed
+    /// with the text.
+    MixedContent(Vec<String>),
+    /// Element content, eg. `(a, (b | c)+)?`
+    ChildrenContent(ContentParticle),
+}
+
+impl ContentSpec {
+    pub unsafe fn from_ptr(ty: c_int, content: *const ffi::xmlElementContent) -> ContentSpec {
+        match ty {
+            ffi::XML_ELEMENT_TYPE_EMPTY => EmptyContent,
+            ffi::XML_ELEMENT_TYPE_MIXED => {
+                let mut names = Vec::new();
+                collect_names(content, &mut names);
+                MixedContent(names)
+            }
+            ffi::XML_ELEMENT_TYPE_ELEMENT if !content.is_null() => {
+                ChildrenContent(ContentParticle::from_ptr(content))
+            }
+            _ => AnyContent,
+        }
+    }
+}
+
+unsafe fn collect_names(content: *const ffi::xmlElementContent, names: &mut Vec<String>) {
+    if content.is_null() { return }
+    match (*content).ty {
+        ffi::XML_ELEMENT_CONTENT_ELEMENT => names.push(qualified_name(content)),
+        _ => {
+            collect_names((*content).c1, names);
+            collect_names((*content).c2, names);
+        }
+    }
+}
+
+unsafe fn qualified_name(content: *const ffi::xmlElementContent) -> String {
+    let name = string::raw::from_buf((*content).name as *const u8);
+    match from_buf_opt((*content).prefix) {
+        Some(prefix) => format!("{}:{}", prefix, name),
+        None => name,
+    }
+}
+
+impl fmt::Show for ContentSpec {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            EmptyContent => write!(fmt, "EMPTY"),
+            AnyContent => write!(fmt, "ANY"),
+            MixedContent(ref names) if names.is_empty() => write!(fmt, "(#PCDATA)"),
+            MixedContent(ref names) => {
+                try!(write!(fmt, "(#PCDATA"));
+                for name in names.iter() {
+                    try!(write!(fmt, "|{}", *name));
+                }
+                write!(fmt, ")*")
+            }
+            ChildrenContent(ref particle) => write!(fmt, "{}", *particle),
+        }
+    }
+}
+
+/// A part of an element content model.
+#[deriving(PartialEq, Eq, Clone)]
+pub enum ContentParticle {
+    /// A child element
+    ElementParticle(String, Occurrence),
+    /// A sequence of particles, eg. `(a, b)`
+    SeqParticle(Vec<ContentParticle>, Occurrence),
+    /// A choice between particles, eg. `(a | b)`
+    ChoiceParticle(Vec<ContentParticle>, Occurrence),
+}
+
+impl ContentParticle {
+    /// libxml2 stores groups as binary trees, so `(a, b, c)` is a sequence
+    /// of `a` and the sequence `(b, c)`. These are flattened back out.
+    unsafe fn from_ptr(content: *const ffi::xmlElementContent) -> ContentParticle {
+        let ocur = Occurrence::from_constant((*content).ocur);
+        match (*content).ty {
+            ffi::XML_ELEMENT_CONTENT_SEQ => {
+                let mut particles = Vec::new();
+                flatten(content, ffi::XML_ELEMENT_CONTENT_SEQ, &mut particles);
+                SeqParticle(particles, ocur)
+            }
+            ffi::XML_ELEMENT_CONTENT_OR => {
+                let mut particles = Vec::new();
+                flatten(content, ffi::XML_ELEMENT_CONTENT_OR, &mut particles);
+                ChoiceParticle(particles, ocur)
+            }
+            _ => ElementParticle(qualified_name(content), ocur),
+        }
+    }
+}
+
+unsafe fn flatten(content: *const ffi::xmlElementContent, ty: c_int,
+                  particles: &mut Vec<ContentParticle>) {
+    particles.push(ContentParticle::from_ptr((*content).c1));
+    let rest = (*content).c2;
+    if (*rest).ty == ty && (*rest).ocur == ffi::XML_ELEMENT_CONTENT_ONCE {
+        flatten(rest, ty, particles);
+    } else {
+        particles.push(ContentParticle::from_ptr(rest));
+    }
+}
+
+impl fmt::Show for ContentParticle {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let (particles, sep, ocur) = match *self {
+            ElementParticle(ref name, ref ocur) => return write!(fmt, "{}{}", *name, *ocur),
+            SeqParticle(ref particles, ref ocur) => (particles, ",", ocur),
+            ChoiceParticle(ref particles, ref ocur) => (particles, "|", ocur),
+        };
+        try!(write!(fmt, "("));
+        for (i, particle) in particles.iter().enumerate() {
+            if i > 0 { try!(write!(fmt, "{}", sep)); }
+            try!(write!(fmt, "{}", *particle));
+        }
+        write!(fmt, "){}", *ocur)
+    }
+}
+
+/// The type of an attribute declared in an `<!ATTLIST>`.
+#[deriving(PartialEq, Eq, Clone, Show)]
+pub enum AttributeType {
+    CData,
+    Id,
+    IdRef,
+    IdRefs,
+    Entity,
+    Entities,
+    NmToken,
+    NmTokens,
+    /// An enumeration of the allowed values
+    Enumeration(Vec<String>),
+    /// An enumeration of notation names
+    Notation(Vec<String>),
+}
+
+/// The default of an attribute declared in an `<!ATTLIST>`.
+#[deriving(PartialEq, Eq, Clone, Show)]
+pub enum AttributeDefault {
+    /// `#REQUIRED`
+    Required,
+    /// `#IMPLIED`
+    Implied,
+    /// `#FIXED "value"`
+    FixedValue(String),
+    /// A default value
+    DefaultValue(String),
+}
+
+/// A single attribute declared in an `<!ATTLIST>`.
+#[deriving(PartialEq, Eq, Clone)]
+pub struct AttributeDef {
+    /// The element the attribute belongs to
+    pub element: String,
+    /// The qualified name of the attribute
+    pub name: String,
+    pub ty: AttributeType,
+    pub default: AttributeDefault,
+}
+
+impl AttributeDef {
+    pub unsafe fn from_buf(elem: *const ffi::xmlChar, fullname: *const ffi::xmlChar,
+                           ty: c_int, def: c_int, default_value: *const ffi::xmlChar,
+                           tree: *const ffi::xmlEnumeration) -> AttributeDef {
+        let ty = match ty {
+            ffi::XML_ATTRIBUTE_ID           => Id,
+            ffi::XML_ATTRIBUTE_IDREF        => IdRef,
+            ffi::XML_ATTRIBUTE_IDREFS       => IdRefs,
+            ffi::XML_ATTRIBUTE_ENTITY       => Entity,
+            ffi::XML_ATTRIBUTE_ENTITIES     => Entities,
+            ffi::XML_ATTRIBUTE_NMTOKEN      => NmToken,
+            ffi::XML_ATTRIBUTE_NMTOKENS     => NmTokens,
+            ffi::XML_ATTRIBUTE_ENUMERATION  => Enumeration(enumeration(tree)),
+            ffi::XML_ATTRIBUTE_NOTATION     => Notation(enumeration(tree)),
+            _                               => CData,
+        };
+        let value = from_buf_opt(default_value).unwrap_or(String::new());
+        let default = match def {
+            ffi::XML_ATTRIBUTE_REQUIRED     => Required,
+            ffi::XML_ATTRIBUTE_IMPLIED      => Implied,
+            ffi::XML_ATTRIBUTE_FIXED        => FixedValue(value),
+            _                               => DefaultValue(value),
+        };
+        AttributeDef {
+            element:    string::raw::from_buf(elem as *const u8),
+            name:       string::raw::from_buf(fullname as *const u8),
+            ty:         ty,
+            default:    default,
+        }
+    }
+}
+
+unsafe fn enumeration(mut tree: *const ffi::xmlEnumeration) -> Vec<String> {
+    let mut values = Vec::new();
+    while !tree.is_null() {
+        values.push(string::raw::from_buf((*tree).name as *const u8));
+        tree = (*tree).next;
+    }
+    values
+}
+
+impl fmt::Show for AttributeDef {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        try!(write!(fmt, "<!ATTLIST {} {} ", self.element, self.name));
+        try!(match self.ty {
+            CData => write!(fmt, "CDATA"),
+            Id => write!(fmt, "ID"),
+            IdRef => write!(fmt, "IDREF"),
+            IdRefs => write!(fmt, "IDREFS"),
+            Entity => write!(fmt, "ENTITY"),
+            Entities => write!(fmt, "ENTITIES"),
+            NmToken => write!(fmt, "NMTOKEN"),
+            NmTokens => write!(fmt, "NMTOKENS"),
+            Enumeration(ref values) => write!(fmt, "({})", values.as_slice().connect("|")),
+            Notation(ref values) => write!(fmt, "NOTATION ({})", values.as_slice().connect("|")),
+        });
+        match self.default {
+            Required => write!(fmt, " #REQUIRED>"),
+            Implied => write!(fmt, " #IMPLIED>"),
+            FixedValue(ref value) => write!(fmt, " #FIXED \"{}\">", *value),
+            DefaultValue(ref value) => write!(fmt, " \"{}\">", *value),
+        }
+    }
+}
+
+/// The kind of entity declared in an `<!ENTITY>`.
+#[deriving(PartialEq, Eq, Clone, Show)]
+pub enum EntityKind {
+    InternalGeneralEntity,
+    ExternalGeneralParsedEntity,
+    ExternalGeneralUnparsedEntity,
+    InternalParameterEntity,
+    ExternalParameterEntity,
+    InternalPredefinedEntity,
+}
+
+/// A parsed entity declared in an `<!ENTITY>`.
+#[deriving(PartialEq, Eq, Clone)]
+pub struct EntityDef {
+    pub name: String,
+    pub kind: EntityKind,
+    pub external_id: ExternalId,
+    /// The replacement text, for internal entities
+    pub content: Option<String>,
+}
+
+impl EntityDef {
+    pub unsafe fn from_buf(name: *const ffi::xmlChar, ty: c_int,
+                           public_id: *const ffi::xmlChar, system_id: *const ffi::xmlChar,
+                           content: *const ffi::xmlChar) -> EntityDef {
+        let kind = match ty {
+            ffi::XML_EXTERNAL_GENERAL_PARSED_ENTITY     => ExternalGeneralParsedEntity,
+            ffi::XML_EXTERNAL_GENERAL_UNPARSED_ENTITY   => ExternalGeneralUnparsedEntity,
+            ffi::XML_INTERNAL_PARAMETER_ENTITY          => InternalParameterEntity,
+            ffi::XML_EXTERNAL_PARAMETER_ENTITY          => ExternalParameterEntity,
+            ffi::XML_INTERNAL_PREDEFINED_ENTITY         => InternalPredefinedEntity,
+            _                                           => InternalGeneralEntity,
+        };
+        EntityDef {
+            name:           string::raw::from_buf(name as *const u8),
+            kind:           kind,
+            external_id:    ExternalId::from_buf(public_id, system_id),
+            content:        from_buf_opt(content),
+        }
+    }
+}
+
+impl fmt::Show for EntityDef {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let percent = match self.kind {
+            InternalParameterEntity | ExternalParameterEntity => "% ",
+            _ => "",
+        };
+        try!(write!(fmt, "<!ENTITY {}{}{}", percent, self.name, self.external_id));
+        match self.content {
+            Some(ref content) => write!(fmt, " \"{}\">", *content),
+            None => write!(fmt, ">"),
+        }
+    }
+}
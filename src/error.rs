@@ -19,6 +19,7 @@ use std::fmt;
 use std::string;
 
 use super::ffi;
+use super::from_buf_opt;
 
 /// The severity of the error
 #[deriving(Clone, PartialEq, Eq, Show)]
@@ -42,26 +43,683 @@ impl ErrorLevel {
     }
 }
 
+/// The part of libxml2 that raised an error, from `xmlErrorDomain`.
+#[deriving(Clone, PartialEq, Eq, Show)]
+pub enum ErrorDomain {
+    /// `XML_FROM_NONE`
+    NoDomain,
+    /// `XML_FROM_PARSER`
+    ParserDomain,
+    /// `XML_FROM_TREE`
+    TreeDomain,
+    /// `XML_FROM_NAMESPACE`
+    NamespaceDomain,
+    /// `XML_FROM_DTD`
+    DtdDomain,
+    /// `XML_FROM_HTML`
+    HtmlDomain,
+    /// `XML_FROM_MEMORY`
+    MemoryDomain,
+    /// `XML_FROM_OUTPUT`
+    OutputDomain,
+    /// `XML_FROM_IO`
+    IoDomain,
+    /// `XML_FROM_FTP`
+    FtpDomain,
+    /// `XML_FROM_HTTP`
+    HttpDomain,
+    /// `XML_FROM_XINCLUDE`
+    XIncludeDomain,
+    /// `XML_FROM_XPATH`
+    XPathDomain,
+    /// `XML_FROM_XPOINTER`
+    XPointerDomain,
+    /// `XML_FROM_REGEXP`
+    RegexpDomain,
+    /// `XML_FROM_DATATYPE`
+    DatatypeDomain,
+    /// `XML_FROM_SCHEMASP`
+    SchemasParserDomain,
+    /// `XML_FROM_SCHEMASV`
+    SchemasValidDomain,
+    /// `XML_FROM_RELAXNGP`
+    RelaxNgParserDomain,
+    /// `XML_FROM_RELAXNGV`
+    RelaxNgValidDomain,
+    /// `XML_FROM_CATALOG`
+    CatalogDomain,
+    /// `XML_FROM_C14N`
+    C14nDomain,
+    /// `XML_FROM_XSLT`
+    XsltDomain,
+    /// `XML_FROM_VALID`
+    ValidDomain,
+    /// `XML_FROM_CHECK`
+    CheckDomain,
+    /// `XML_FROM_WRITER`
+    WriterDomain,
+    /// `XML_FROM_MODULE`
+    ModuleDomain,
+    /// `XML_FROM_I18N`
+    I18nDomain,
+    /// `XML_FROM_SCHEMATRONV`
+    SchematronDomain,
+    /// `XML_FROM_BUFFER`
+    BufferDomain,
+    /// `XML_FROM_URI`
+    UriDomain,
+    /// Any other `xmlErrorDomain` value
+    OtherDomain(i32),
+}
+
+impl ErrorDomain {
+    /// Maps an `xmlErrorDomain` value to its `ErrorDomain`.
+    pub fn from_constant(value: i32) -> ErrorDomain {
+        match value {
+            0  => NoDomain,
+            1  => ParserDomain,
+            2  => TreeDomain,
+            3  => NamespaceDomain,
+            4  => DtdDomain,
+            5  => HtmlDomain,
+            6  => MemoryDomain,
+            7  => OutputDomain,
+            8  => IoDomain,
+            9  => FtpDomain,
+            10 => HttpDomain,
+            11 => XIncludeDomain,
+            12 => XPathDomain,
+            13 => XPointerDomain,
+            14 => RegexpDomain,
+            15 => DatatypeDomain,
+            16 => SchemasParserDomain,
+            17 => SchemasValidDomain,
+            18 => RelaxNgParserDomain,
+            19 => RelaxNgValidDomain,
+            20 => CatalogDomain,
+            21 => C14nDomain,
+            22 => XsltDomain,
+            23 => ValidDomain,
+            24 => CheckDomain,
+            25 => WriterDomain,
+            26 => ModuleDomain,
+            27 => I18nDomain,
+            28 => SchematronDomain,
+            29 => BufferDomain,
+            30 => UriDomain,
+            _  => OtherDomain(value),
+        }
+    }
+}
+
+
+/// The error codes of libxml2's `xmlParserErrors`, as far as they concern
+/// parsing. Any other code is kept as `Other`.
+#[deriving(Clone, PartialEq, Eq, Show)]
+pub enum ErrorCode {
+    /// `XML_ERR_OK`
+    NoError,
+    /// `XML_ERR_INTERNAL_ERROR`
+    InternalError,
+    /// `XML_ERR_NO_MEMORY`
+    NoMemory,
+    /// `XML_ERR_DOCUMENT_START`
+    DocumentStart,
+    /// `XML_ERR_DOCUMENT_EMPTY`
+    DocumentEmpty,
+    /// `XML_ERR_DOCUMENT_END`
+    DocumentEnd,
+    /// `XML_ERR_INVALID_HEX_CHARREF`
+    InvalidHexCharRef,
+    /// `XML_ERR_INVALID_DEC_CHARREF`
+    InvalidDecCharRef,
+    /// `XML_ERR_INVALID_CHARREF`
+    InvalidCharRef,
+    /// `XML_ERR_INVALID_CHAR`
+    InvalidChar,
+    /// `XML_ERR_CHARREF_AT_EOF`
+    CharRefAtEof,
+    /// `XML_ERR_CHARREF_IN_PROLOG`
+    CharRefInProlog,
+    /// `XML_ERR_CHARREF_IN_EPILOG`
+    CharRefInEpilog,
+    /// `XML_ERR_CHARREF_IN_DTD`
+    CharRefInDtd,
+    /// `XML_ERR_ENTITYREF_AT_EOF`
+    EntityRefAtEof,
+    /// `XML_ERR_ENTITYREF_IN_PROLOG`
+    EntityRefInProlog,
+    /// `XML_ERR_ENTITYREF_IN_EPILOG`
+    EntityRefInEpilog,
+    /// `XML_ERR_ENTITYREF_IN_DTD`
+    EntityRefInDtd,
+    /// `XML_ERR_PEREF_AT_EOF`
+    PeRefAtEof,
+    /// `XML_ERR_PEREF_IN_PROLOG`
+    PeRefInProlog,
+    /// `XML_ERR_PEREF_IN_EPILOG`
+    PeRefInEpilog,
+    /// `XML_ERR_PEREF_IN_INT_SUBSET`
+    PeRefInIntSubset,
+    /// `XML_ERR_ENTITYREF_NO_NAME`
+    EntityRefNoName,
+    /// `XML_ERR_ENTITYREF_SEMICOL_MISSING`
+    EntityRefSemicolonMissing,
+    /// `XML_ERR_PEREF_NO_NAME`
+    PeRefNoName,
+    /// `XML_ERR_PEREF_SEMICOL_MISSING`
+    PeRefSemicolonMissing,
+    /// `XML_ERR_UNDECLARED_ENTITY`
+    UndeclaredEntity,
+    /// `XML_WAR_UNDECLARED_ENTITY`
+    UndeclaredEntityWarning,
+    /// `XML_ERR_UNPARSED_ENTITY`
+    UnparsedEntity,
+    /// `XML_ERR_ENTITY_IS_EXTERNAL`
+    EntityIsExternal,
+    /// `XML_ERR_ENTITY_IS_PARAMETER`
+    EntityIsParameter,
+    /// `XML_ERR_UNKNOWN_ENCODING`
+    UnknownEncoding,
+    /// `XML_ERR_UNSUPPORTED_ENCODING`
+    UnsupportedEncoding,
+    /// `XML_ERR_STRING_NOT_STARTED`
+    StringNotStarted,
+    /// `XML_ERR_STRING_NOT_CLOSED`
+    StringNotClosed,
+    /// `XML_ERR_NS_DECL_ERROR`
+    NsDeclError,
+    /// `XML_ERR_ENTITY_NOT_STARTED`
+    EntityNotStarted,
+    /// `XML_ERR_ENTITY_NOT_FINISHED`
+    EntityNotFinished,
+    /// `XML_ERR_LT_IN_ATTRIBUTE`
+    LtInAttribute,
+    /// `XML_ERR_ATTRIBUTE_NOT_STARTED`
+    AttributeNotStarted,
+    /// `XML_ERR_ATTRIBUTE_NOT_FINISHED`
+    AttributeNotFinished,
+    /// `XML_ERR_ATTRIBUTE_WITHOUT_VALUE`
+    AttributeWithoutValue,
+    /// `XML_ERR_ATTRIBUTE_REDEFINED`
+    AttributeRedefined,
+    /// `XML_ERR_LITERAL_NOT_STARTED`
+    LiteralNotStarted,
+    /// `XML_ERR_LITERAL_NOT_FINISHED`
+    LiteralNotFinished,
+    /// `XML_ERR_COMMENT_NOT_FINISHED`
+    CommentNotFinished,
+    /// `XML_ERR_PI_NOT_STARTED`
+    PiNotStarted,
+    /// `XML_ERR_PI_NOT_FINISHED`
+    PiNotFinished,
+    /// `XML_ERR_NOTATION_NOT_STARTED`
+    NotationNotStarted,
+    /// `XML_ERR_NOTATION_NOT_FINISHED`
+    NotationNotFinished,
+    /// `XML_ERR_ATTLIST_NOT_STARTED`
+    AttlistNotStarted,
+    /// `XML_ERR_ATTLIST_NOT_FINISHED`
+    AttlistNotFinished,
+    /// `XML_ERR_MIXED_NOT_STARTED`
+    MixedNotStarted,
+    /// `XML_ERR_MIXED_NOT_FINISHED`
+    MixedNotFinished,
+    /// `XML_ERR_ELEMCONTENT_NOT_STARTED`
+    ElemContentNotStarted,
+    /// `XML_ERR_ELEMCONTENT_NOT_FINISHED`
+    ElemContentNotFinished,
+    /// `XML_ERR_XMLDECL_NOT_STARTED`
+    XmlDeclNotStarted,
+    /// `XML_ERR_XMLDECL_NOT_FINISHED`
+    XmlDeclNotFinished,
+    /// `XML_ERR_CONDSEC_NOT_STARTED`
+    CondSecNotStarted,
+    /// `XML_ERR_CONDSEC_NOT_FINISHED`
+    CondSecNotFinished,
+    /// `XML_ERR_EXT_SUBSET_NOT_FINISHED`
+    ExtSubsetNotFinished,
+    /// `XML_ERR_DOCTYPE_NOT_FINISHED`
+    DoctypeNotFinished,
+    /// `XML_ERR_MISPLACED_CDATA_END`
+    MisplacedCdataEnd,
+    /// `XML_ERR_CDATA_NOT_FINISHED`
+    CdataNotFinished,
+    /// `XML_ERR_RESERVED_XML_NAME`
+    ReservedXmlName,
+    /// `XML_ERR_SPACE_REQUIRED`
+    SpaceRequired,
+    /// `XML_ERR_SEPARATOR_REQUIRED`
+    SeparatorRequired,
+    /// `XML_ERR_NMTOKEN_REQUIRED`
+    NmtokenRequired,
+    /// `XML_ERR_NAME_REQUIRED`
+    NameRequired,
+    /// `XML_ERR_PCDATA_REQUIRED`
+    PcdataRequired,
+    /// `XML_ERR_URI_REQUIRED`
+    UriRequired,
+    /// `XML_ERR_PUBID_REQUIRED`
+    PubidRequired,
+    /// `XML_ERR_LT_REQUIRED`
+    LtRequired,
+    /// `XML_ERR_GT_REQUIRED`
+    GtRequired,
+    /// `XML_ERR_LTSLASH_REQUIRED`
+    LtSlashRequired,
+    /// `XML_ERR_EQUAL_REQUIRED`
+    EqualRequired,
+    /// `XML_ERR_TAG_NAME_MISMATCH`
+    TagNameMismatch,
+    /// `XML_ERR_TAG_NOT_FINISHED`
+    TagNotFinished,
+    /// `XML_ERR_STANDALONE_VALUE`
+    StandaloneValue,
+    /// `XML_ERR_ENCODING_NAME`
+    EncodingName,
+    /// `XML_ERR_HYPHEN_IN_COMMENT`
+    HyphenInComment,
+    /// `XML_ERR_INVALID_ENCODING`
+    InvalidEncoding,
+    /// `XML_ERR_EXT_ENTITY_STANDALONE`
+    ExtEntityStandalone,
+    /// `XML_ERR_CONDSEC_INVALID`
+    CondSecInvalid,
+    /// `XML_ERR_VALUE_REQUIRED`
+    ValueRequired,
+    /// `XML_ERR_NOT_WELL_BALANCED`
+    NotWellBalanced,
+    /// `XML_ERR_EXTRA_CONTENT`
+    ExtraContent,
+    /// `XML_ERR_ENTITY_CHAR_ERROR`
+    EntityCharError,
+    /// `XML_ERR_ENTITY_PE_INTERNAL`
+    EntityPeInternal,
+    /// `XML_ERR_ENTITY_LOOP`
+    EntityLoop,
+    /// `XML_ERR_ENTITY_BOUNDARY`
+    EntityBoundary,
+    /// `XML_ERR_INVALID_URI`
+    InvalidUri,
+    /// `XML_ERR_URI_FRAGMENT`
+    UriFragment,
+    /// `XML_WAR_CATALOG_PI`
+    CatalogPiWarning,
+    /// `XML_ERR_NO_DTD`
+    NoDtd,
+    /// `XML_ERR_CONDSEC_INVALID_KEYWORD`
+    CondSecInvalidKeyword,
+    /// `XML_ERR_VERSION_MISSING`
+    VersionMissing,
+    /// `XML_WAR_UNKNOWN_VERSION`
+    UnknownVersionWarning,
+    /// `XML_WAR_LANG_VALUE`
+    LangValueWarning,
+    /// `XML_WAR_NS_URI`
+    NsUriWarning,
+    /// `XML_WAR_NS_URI_RELATIVE`
+    NsUriRelativeWarning,
+    /// `XML_ERR_MISSING_ENCODING`
+    MissingEncoding,
+    /// `XML_WAR_SPACE_VALUE`
+    SpaceValueWarning,
+    /// `XML_ERR_NOT_STANDALONE`
+    NotStandalone,
+    /// `XML_ERR_ENTITY_PROCESSING`
+    EntityProcessing,
+    /// `XML_ERR_NOTATION_PROCESSING`
+    NotationProcessing,
+    /// `XML_WAR_NS_COLUMN`
+    NsColumnWarning,
+    /// `XML_WAR_ENTITY_REDEFINED`
+    EntityRedefinedWarning,
+    /// `XML_ERR_UNKNOWN_VERSION`
+    UnknownVersion,
+    /// `XML_ERR_VERSION_MISMATCH`
+    VersionMismatch,
+    /// `XML_ERR_NAME_TOO_LONG`
+    NameTooLong,
+    /// `XML_ERR_USER_STOP`
+    UserStop,
+    /// `XML_NS_ERR_XML_NAMESPACE`
+    NsXmlNamespace,
+    /// `XML_NS_ERR_UNDEFINED_NAMESPACE`
+    NsUndefinedNamespace,
+    /// `XML_NS_ERR_QNAME`
+    NsQName,
+    /// `XML_NS_ERR_ATTRIBUTE_REDEFINED`
+    NsAttributeRedefined,
+    /// `XML_NS_ERR_EMPTY`
+    NsEmpty,
+    /// `XML_NS_ERR_COLON`
+    NsColon,
+    /// `XML_HTML_STRUCURE_ERROR`
+    HtmlStructureError,
+    /// `XML_HTML_UNKNOWN_TAG`
+    HtmlUnknownTag,
+    /// `XML_IO_NETWORK_ATTEMPT`
+    IoNetworkAttempt,
+    /// `XML_IO_LOAD_ERROR`
+    IoLoadError,
+    /// Any other `xmlParserErrors` value
+    Other(i32),
+}
+
+impl ErrorCode {
+    /// Maps an `xmlParserErrors` value to its `ErrorCode`.
+    pub fn from_constant(value: i32) -> ErrorCode {
+        match value {
+            0    => NoError,
+            1    => InternalError,
+            2    => NoMemory,
+            3    => DocumentStart,
+            4    => DocumentEmpty,
+            5    => DocumentEnd,
+            6    => InvalidHexCharRef,
+            7    => InvalidDecCharRef,
+            8    => InvalidCharRef,
+            9    => InvalidChar,
+            10   => CharRefAtEof,
+            11   => CharRefInProlog,
+            12   => CharRefInEpilog,
+            13   => CharRefInDtd,
+            14   => EntityRefAtEof,
+            15   => EntityRefInProlog,
+            16   => EntityRefInEpilog,
+            17   => EntityRefInDtd,
+            18   => PeRefAtEof,
+            19   => PeRefInProlog,
+            20   => PeRefInEpilog,
+            21   => PeRefInIntSubset,
+            22   => EntityRefNoName,
+            23   => EntityRefSemicolonMissing,
+            24   => PeRefNoName,
+            25   => PeRefSemicolonMissing,
+            26   => UndeclaredEntity,
+            27   => UndeclaredEntityWarning,
+            28   => UnparsedEntity,
+            29   => EntityIsExternal,
+            30   => EntityIsParameter,
+            31   => UnknownEncoding,
+            32   => UnsupportedEncoding,
+            33   => StringNotStarted,
+            34   => StringNotClosed,
+            35   => NsDeclError,
+            36   => EntityNotStarted,
+            37   => EntityNotFinished,
+            38   => LtInAttribute,
+            39   => AttributeNotStarted,
+            40   => AttributeNotFinished,
+            41   => AttributeWithoutValue,
+            42   => AttributeRedefined,
+            43   => LiteralNotStarted,
+            44   => LiteralNotFinished,
+            45   => CommentNotFinished,
+            46   => PiNotStarted,
+            47   => PiNotFinished,
+            48   => NotationNotStarted,
+            49   => NotationNotFinished,
+            50   => AttlistNotStarted,
+            51   => AttlistNotFinished,
+            52   => MixedNotStarted,
+            53   => MixedNotFinished,
+            54   => ElemContentNotStarted,
+            55   => ElemContentNotFinished,
+            56   => XmlDeclNotStarted,
+            57   => XmlDeclNotFinished,
+            58   => CondSecNotStarted,
+            59   => CondSecNotFinished,
+            60   => ExtSubsetNotFinished,
+            61   => DoctypeNotFinished,
+            62   => MisplacedCdataEnd,
+            63   => CdataNotFinished,
+            64   => ReservedXmlName,
+            65   => SpaceRequired,
+            66   => SeparatorRequired,
+            67   => NmtokenRequired,
+            68   => NameRequired,
+            69   => PcdataRequired,
+            70   => UriRequired,
+            71   => PubidRequired,
+            72   => LtRequired,
+            73   => GtRequired,
+            74   => LtSlashRequired,
+            75   => EqualRequired,
+            76   => TagNameMismatch,
+            77   => TagNotFinished,
+            78   => StandaloneValue,
+            79   => EncodingName,
+            80   => HyphenInComment,
+            81   => InvalidEncoding,
+            82   => ExtEntityStandalone,
+            83   => CondSecInvalid,
+            84   => ValueRequired,
+            85   => NotWellBalanced,
+            86   => ExtraContent,
+            87   => EntityCharError,
+            88   => EntityPeInternal,
+            89   => EntityLoop,
+            90   => EntityBoundary,
+            91   => InvalidUri,
+            92   => UriFragment,
+            93   => CatalogPiWarning,
+            94   => NoDtd,
+            95   => CondSecInvalidKeyword,
+            96   => VersionMissing,
+            97   => UnknownVersionWarning,
+            98   => LangValueWarning,
+            99   => NsUriWarning,
+            100  => NsUriRelativeWarning,
+            101  => MissingEncoding,
+            102  => SpaceValueWarning,
+            103  => NotStandalone,
+            104  => EntityProcessing,
+            105  => NotationProcessing,
+            106  => NsColumnWarning,
+            107  => EntityRedefinedWarning,
+            108  => UnknownVersion,
+            109  => VersionMismatch,
+            110  => NameTooLong,
+            111  => UserStop,
+            200  => NsXmlNamespace,
+            201  => NsUndefinedNamespace,
+            202  => NsQName,
+            203  => NsAttributeRedefined,
+            204  => NsEmpty,
+            205  => NsColon,
+            800  => HtmlStructureError,
+            801  => HtmlUnknownTag,
+            1543 => IoNetworkAttempt,
+            1549 => IoLoadError,
+            _    => Other(value),
+        }
+    }
+}
+
+/// A way in which a document broke the limits set by the `ParserConfig`
+#[deriving(Clone, PartialEq, Eq)]
+pub enum Violation {
+    /// A resource had to be fetched over the network
+    NetworkAccess,
+    /// A reference was made to the named external entity
+    ExternalEntity(String),
+    /// Entity references expanded to too much text for the size of the input
+    EntityExpansion,
+    /// References nested too deeply within the named entity
+    EntityDepth(String),
+}
+
+impl fmt::Show for Violation {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            NetworkAccess => write!(fmt, "network access is disabled"),
+            ExternalEntity(ref name) => write!(fmt, "external entity '{}' may not be loaded", *name),
+            EntityExpansion => write!(fmt, "entity expansion exceeds the permitted ratio"),
+            EntityDepth(ref name) => write!(fmt, "entity '{}' nests too deeply", *name),
+        }
+    }
+}
+
+/// What kind of problem an error reports
+#[deriving(Clone, PartialEq, Eq, Show)]
+pub enum ErrorKind {
+    /// A problem found by libxml2 while parsing the document
+    XmlError,
+    /// The document was rejected by the security limits of the parser
+    SecurityViolation(Violation),
+}
+
 /// An XML parse error
 #[deriving(Clone, PartialEq, Eq)]
 pub struct ErrorData {
+    kind: ErrorKind,
     level: ErrorLevel,
     line: uint,
     column: uint,
     message: String,
+    domain: ErrorDomain,
+    code: ErrorCode,
+    file: Option<String>,
+    str1: Option<String>,
+    str2: Option<String>,
+    str3: Option<String>,
+    int1: i32,
 }
 
 impl ErrorData {
     pub unsafe fn from_ptr(error: *const ffi::xmlError) -> Option<ErrorData> {
         ErrorLevel::from_constant((*error).level).map(|level| {
+            // libxml2 refuses network access itself when `XML_PARSE_NONET`
+            // is set
+            let kind = match (*error).code {
+                ffi::XML_IO_NETWORK_ATTEMPT => SecurityViolation(NetworkAccess),
+                _ => XmlError,
+            };
             ErrorData {
+                kind:       kind,
                 level:      level,
                 message:    string::raw::from_buf((*error).message as *const u8),
                 line:       (*error).line as uint,
                 column:     (*error).int2 as uint,
+                domain:     ErrorDomain::from_constant((*error).domain as i32),
+                code:       ErrorCode::from_constant((*error).code as i32),
+                file:       from_buf_opt((*error).file as *const ffi::xmlChar),
+                str1:       from_buf_opt((*error).str1 as *const ffi::xmlChar),
+                str2:       from_buf_opt((*error).str2 as *const ffi::xmlChar),
+                str3:       from_buf_opt((*error).str3 as *const ffi::xmlChar),
+                int1:       (*error).int1 as i32,
             }
         })
     }
+
+    /// A fatal error for a document that broke the security limits of the
+    /// parser. These did not come from libxml2, so the `domain` and `code`
+    /// are `NoDomain` and `NoError`.
+    pub fn from_violation(violation: Violation, line: uint, column: uint) -> ErrorData {
+        ErrorData {
+            message:    violation.to_string(),
+            kind:       SecurityViolation(violation),
+            level:      Fatal,
+            line:       line,
+            column:     column,
+            domain:     NoDomain,
+            code:       NoError,
+            file:       None,
+            str1:       None,
+            str2:       None,
+            str3:       None,
+            int1:       0,
+        }
+    }
+
+    /// A fatal error raised by this crate rather than by libxml2, for when
+    /// libxml2 fails without saying why.
+    pub fn internal(message: &str) -> ErrorData {
+        ErrorData {
+            message:    message.to_string(),
+            kind:       XmlError,
+            level:      Fatal,
+            line:       0,
+            column:     0,
+            domain:     NoDomain,
+            code:       InternalError,
+            file:       None,
+            str1:       None,
+            str2:       None,
+            str3:       None,
+            int1:       0,
+        }
+    }
+
+    /// Raises a `Warning` to an `Error`, leaving more severe errors as they
+    /// are.
+    pub fn escalate(self) -> ErrorData {
+        match self.level {
+            Warning => ErrorData { level: Error, ..self },
+            _ => self,
+        }
+    }
+
+    /// What kind of problem this error reports.
+    pub fn kind<'a>(&'a self) -> &'a ErrorKind {
+        &self.kind
+    }
+
+    /// The severity of the error.
+    pub fn level(&self) -> ErrorLevel {
+        self.level
+    }
+
+    /// The line the error was found on, or `0` if it is not known.
+    pub fn line(&self) -> uint {
+        self.line
+    }
+
+    /// The column the error was found at, or `0` if it is not known.
+    pub fn column(&self) -> uint {
+        self.column
+    }
+
+    /// The human-readable description of the error.
+    pub fn message<'a>(&'a self) -> &'a str {
+        self.message.as_slice()
+    }
+
+    /// The part of libxml2 that raised the error.
+    pub fn domain(&self) -> ErrorDomain {
+        self.domain
+    }
+
+    /// What went wrong, as one of libxml2's error codes.
+    pub fn code(&self) -> ErrorCode {
+        self.code
+    }
+
+    /// The file being parsed, if libxml2 knows it.
+    pub fn file<'a>(&'a self) -> Option<&'a str> {
+        self.file.as_ref().map(|s| s.as_slice())
+    }
+
+    /// Extra information about the error, such as the name of the element
+    /// or entity involved. What each one holds depends on the `code`.
+    pub fn str1<'a>(&'a self) -> Option<&'a str> {
+        self.str1.as_ref().map(|s| s.as_slice())
+    }
+
+    /// See `str1`.
+    pub fn str2<'a>(&'a self) -> Option<&'a str> {
+        self.str2.as_ref().map(|s| s.as_slice())
+    }
+
+    /// See `str1`.
+    pub fn str3<'a>(&'a self) -> Option<&'a str> {
+        self.str3.as_ref().map(|s| s.as_slice())
+    }
+
+    /// Extra numeric information about the error, depending on the `code`.
+    pub fn int1(&self) -> i32 {
+        self.int1
+    }
 }
 
 impl fmt::Show for ErrorData {
@@ -73,3 +731,52 @@ impl fmt::Show for ErrorData {
                self.message)
     }
 }
+
+/// A summary of the errors reported during a parse.
+#[deriving(Clone, PartialEq, Eq, Show)]
+pub struct ErrorReport {
+    warnings: uint,
+    errors: uint,
+    fatal: uint,
+    collected: Vec<ErrorData>,
+}
+
+impl ErrorReport {
+    pub fn new() -> ErrorReport {
+        ErrorReport { warnings: 0, errors: 0, fatal: 0, collected: Vec::new() }
+    }
+
+    /// Counts an error that was seen at `level`.
+    pub fn add(&mut self, level: ErrorLevel) {
+        match level {
+            Warning => self.warnings += 1,
+            Error   => self.errors += 1,
+            Fatal   => self.fatal += 1,
+        }
+    }
+
+    /// Keeps an error until the end of the parse.
+    pub fn push(&mut self, error: ErrorData) {
+        self.collected.push(error);
+    }
+
+    /// The number of errors that were seen at `level`.
+    pub fn count(&self, level: ErrorLevel) -> uint {
+        match level {
+            Warning => self.warnings,
+            Error   => self.errors,
+            Fatal   => self.fatal,
+        }
+    }
+
+    /// The errors kept by the `CollectErrors` policy, in the order that they
+    /// were reported. This is empty under the other policies.
+    pub fn errors<'a>(&'a self) -> &'a [ErrorData] {
+        self.collected.as_slice()
+    }
+
+    /// Whether no errors were seen at all.
+    pub fn is_empty(&self) -> bool {
+        self.warnings + self.errors + self.fatal == 0
+    }
+}
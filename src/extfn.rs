@@ -15,28 +15,448 @@
 
 //! External callback definitions
 
-use libc::{c_int, c_void};
+use libc::{c_char, c_int, c_void};
+use std::cell::Cell;
+use std::cmp;
+use std::collections::HashMap;
+use std::io;
 use std::mem::transmute;
-use std::comm::Sender;
 use std::ptr::null;
+use std::slice;
 use std::string;
 
 use super::*;
-use super::error::ErrorData;
+use super::error::{ErrorData, ErrorReport, Violation, ExternalEntity, EntityDepth, EntityExpansion};
+use super::error::Fatal;
+use super::{from_buf_opt, str_from_buf, str_from_buf_len, str_from_buf_opt};
+use super::borrowed;
+use super::borrowed::{QNameRef, AttributesRef, NamespacesRef};
+use super::dtd;
 
-pub fn new_handler() -> ffi::xmlSAXHandler {
-    ffi::xmlSAXHandler {
-        internalSubset:         None,
+/// The amount of text that entities may always expand to, however small the
+/// input is.
+static ENTITY_EXPANSION_ALLOWANCE: uint = 1 << 16;
+
+/// A general entity declared in the DTD.
+struct Entity {
+    /// The entity handed to libxml2, or null for an external entity that
+    /// must not be loaded
+    ptr: ffi::xmlEntityPtr,
+    /// The replacement text, still containing any references
+    content: String,
+    /// How deeply references nest within the entity, once it is known
+    depth: Cell<Option<uint>>,
+}
+
+/// The kinds of text that are held back while coalescing.
+#[deriving(PartialEq)]
+enum TextKind {
+    CharacterText,
+    CdataText,
+}
+
+/// Receives the events of a parse that has been stopped.
+struct Discard;
+
+impl SaxHandler for Discard {}
+
+/// The state of a single parse. A pointer to this is passed to libxml2 as
+/// the user data, and is what each callback receives as its `ctx`.
+pub struct Context<'a> {
+    handler: &'a mut SaxHandler,
+    /// The libxml2 parser context, once it has been created
+    ctxt: ffi::xmlParserCtxtPtr,
+    intern_names: bool,
+    /// The parser's dictionary, which names are interned in when
+    /// `intern_names` is enabled, otherwise null
+    dict: ffi::xmlDictPtr,
+    positions: bool,
+    events: EventMask,
+    whitespace: WhitespacePolicy,
+    coalesce: TextCoalescing,
+    /// The text held back to be joined with the text that follows
+    pending: Option<(TextKind, String)>,
+    expand_entities: bool,
+    /// Whether libxml2 needs to load the external subset
+    load_dtd: bool,
+    external_entities: bool,
+    max_entity_depth: uint,
+    max_entity_ratio: uint,
+    /// The general entities declared in the internal subset. Without these
+    /// libxml2 has no way of resolving references in SAX mode.
+    entities: HashMap<String, Entity>,
+    /// The number of bytes of replacement text expanded so far
+    expanded: uint,
+    error_policy: ErrorPolicy,
+    warnings_as_errors: bool,
+    report: ErrorReport,
+    /// The first fatal error, which is what ended the parse
+    fatal: Option<ErrorData>,
+    /// What libxml2 returned at the end of the input, if it got there
+    status: Option<c_int>,
+    /// Set if the handler asked for the parse to be stopped
+    cancelled: bool,
+    /// Set once the parse has been stopped, after which events are discarded
+    stopped: bool,
+    discard: Discard,
+}
+
+impl<'a> Context<'a> {
+    pub fn new(config: &ParserConfig, handler: &'a mut SaxHandler) -> Context<'a> {
+        Context {
+            handler: handler,
+            ctxt: null(),
+            intern_names: config.intern_names,
+            dict: null(),
+            positions: config.positions,
+            events: config.events,
+            whitespace: config.whitespace,
+            coalesce: config.coalesce,
+            pending: None,
+            expand_entities: config.expand_entities,
+            load_dtd: config.options & (ffi::XML_PARSE_DTDLOAD | ffi::XML_PARSE_DTDVALID) != 0,
+            external_entities: config.external_entities,
+            max_entity_depth: config.max_entity_depth,
+            max_entity_ratio: config.max_entity_ratio,
+            entities: HashMap::new(),
+            expanded: 0,
+            error_policy: config.error_policy,
+            warnings_as_errors: config.warnings_as_errors,
+            report: ErrorReport::new(),
+            fatal: None,
+            status: None,
+            cancelled: false,
+            stopped: false,
+            discard: Discard,
+        }
+    }
+
+    /// Sets the parser context that is driving the callbacks, and hands its
+    /// dictionary to the handler if names are to be interned.
+    pub unsafe fn attach(&mut self, ctxt: ffi::xmlParserCtxtPtr) {
+        self.ctxt = ctxt;
+        if self.intern_names && !(*ctxt).dict.is_null() {
+            self.dict = (*ctxt).dict;
+            self.handler.names(&Names::from_dict(self.dict));
+        }
+    }
+
+    /// Frees the parser context, returning how the parse ended and the errors
+    /// that were seen.
+    pub unsafe fn finish(&mut self) -> (ParseOutcome, ErrorReport) {
+        self.flush_text();
+        let outcome = self.outcome();
+        if !self.ctxt.is_null() {
+            free_parser(self.ctxt);
+            self.ctxt = null();
+        }
+        (outcome, self.report.clone())
+    }
+
+    /// Records what libxml2 returned once it reached the end of the input,
+    /// which is zero if the document is well-formed.
+    pub fn parsed(&mut self, status: c_int) {
+        self.status = Some(status);
+    }
+
+    /// Records that the consumer abandoned the parse before it finished.
+    pub fn cancel(&mut self) {
+        self.cancelled = true;
+    }
+
+    /// Whether the parse has been stopped early, after which there is no
+    /// point in feeding it any more input.
+    pub fn is_stopped(&self) -> bool {
+        self.stopped
+    }
+
+    unsafe fn outcome(&self) -> ParseOutcome {
+        if self.cancelled {
+            return Cancelled;
+        }
+        match self.fatal {
+            Some(ref error) => return Failed(error.clone()),
+            None => {}
+        }
+        if self.ctxt.is_null() {
+            return Failed(ErrorData::internal("the parser could not be created"));
+        }
+        match self.status {
+            Some(0) => return Complete,
+            Some(_) => {}
+            None => return Failed(ErrorData::internal("the parse did not reach the end")),
+        }
+        // libxml2 gave up without reporting a fatal error to `serror`
+        let error = ffi::xmlCtxtGetLastError(self.ctxt);
+        Failed(if error.is_null() { None } else { ErrorData::from_ptr(error) }
+            .unwrap_or_else(|| ErrorData::internal("the document is not well-formed")))
+    }
+
+    /// Gets the handler for dispatching an event, first sending any text that
+    /// has been held back, and telling it where the event is if positions
+    /// are enabled. Once the parse has been stopped,
+    /// either by the handler or for breaking a limit, events are discarded
+    /// instead.
+    unsafe fn handler<'b>(&'b mut self) -> &'b mut SaxHandler {
+        if !self.stopped && self.handler.should_stop() {
+            self.cancel();
+            self.stop();
+        }
+        if self.stopped {
+            return &mut self.discard as &mut SaxHandler;
+        }
+        if self.pending.is_some() {
+            self.flush_text();
+        }
+        if self.positions {
+            self.position().map(|position| self.handler.set_position(position));
+        }
+        &mut *self.handler
+    }
+
+    /// Passes on some text, or holds it back to be joined with the text that
+    /// follows when coalescing is enabled. Character text is always held back
+    /// unless whitespace is preserved, since whether it is blank depends on
+    /// the whole run up to the next markup.
+    unsafe fn text(&mut self, kind: TextKind, text: &str) {
+        if self.stopped { return }
+        let kind = match (self.coalesce, kind) {
+            (SplitText, CharacterText) if self.whitespace != PreserveWhitespace => CharacterText,
+            (SplitText, kind) => return self.dispatch_text(kind, text),
+            (MergeText, kind) => kind,
+            (MergeTextAndCdata, _) => CharacterText,
+        };
+        let joins = match self.pending {
+            Some((pending, _)) => pending == kind,
+            None => true,
+        };
+        if !joins {
+            self.flush_text();
+        }
+        // don't hold on to text that could only become masked-out events
+        let kinds = match (kind, self.whitespace) {
+            (CdataText, _) => CdataEvents,
+            (CharacterText, ReportWhitespace) => CharacterEvents | WhitespaceEvents,
+            (CharacterText, _) => CharacterEvents,
+        };
+        if !self.events.intersects(kinds) {
+            return;
+        }
+        if self.pending.is_none() {
+            self.pending = Some((kind, String::new()));
+        }
+        match self.pending {
+            Some((_, ref mut pending)) => pending.push_str(text),
+            None => {}
+        }
+    }
+
+    /// Sends the text that has been held back, if there is any.
+    unsafe fn flush_text(&mut self) {
+        match self.pending.take() {
+            Some((kind, text)) => self.dispatch_text(kind, text.as_slice()),
+            None => {}
+        }
+    }
+
+    /// Sends some text to the handler, according to the whitespace policy.
+    unsafe fn dispatch_text(&mut self, kind: TextKind, text: &str) {
+        let blank = kind == CharacterText && is_whitespace(text);
+        match (self.whitespace, kind) {
+            (_, CdataText) => self.dispatch(borrowed::CdataBlock(text)),
+            (StripWhitespace, _) if blank => {}
+            (ReportWhitespace, _) if blank => self.dispatch(borrowed::IgnorableWhitespace(text)),
+            _ => self.dispatch(borrowed::Characters(text)),
+        }
+    }
+
+    /// Whether the handler is interested in every kind of event in `kinds`.
+    fn wants(&self, kinds: EventMask) -> bool {
+        self.events.contains(kinds)
+    }
+
+    /// Sends an event to the handler, unless its kind has been masked out.
+    /// Any text that has been held back is sent first either way, since the
+    /// event ends the run.
+    unsafe fn dispatch(&mut self, event: ParseEventRef) {
+        if self.pending.is_some() {
+            self.flush_text();
+        }
+        if self.wants(event_kind(&event)) {
+            self.handler().event(event);
+        }
+    }
+
+    /// Counts an error, then deals with it according to the error policy.
+    unsafe fn error(&mut self, error: ErrorData) {
+        if self.stopped { return }
+        let error = if self.warnings_as_errors { error.escalate() } else { error };
+        self.report.add(error.level());
+        if error.level() == Fatal && self.fatal.is_none() {
+            self.fatal = Some(error.clone());
+        }
+        let policy = self.error_policy;
+        match policy {
+            ReportErrors => self.handler().error(&error),
+            CollectErrors => self.report.push(error),
+            FailFast => {
+                self.handler().error(&error);
+                if error.level() == Fatal {
+                    self.stop();
+                }
+            }
+        }
+    }
+
+    fn position(&self) -> Option<Position> {
+        if self.ctxt.is_null() { return None }
+        unsafe {
+            Some(Position {
+                line:           ffi::xmlSAX2GetLineNumber(self.ctxt) as uint,
+                column:         ffi::xmlSAX2GetColumnNumber(self.ctxt) as uint,
+                byte_offset:    ffi::xmlByteConsumed(self.ctxt) as uint,
+            })
+        }
+    }
+
+    /// Whether libxml2 has to build a document to hold the DTD, which it
+    /// needs for loading the external subset and external entities.
+    fn needs_document(&self) -> bool {
+        (self.load_dtd || self.external_entities) && !self.ctxt.is_null()
+    }
+
+    /// Looks up the entity for a reference that libxml2 is about to expand,
+    /// checking it against the limits of the configuration.
+    unsafe fn entity(&mut self, name: *const ffi::xmlChar) -> Result<ffi::xmlEntityPtr, Violation> {
+        let key = string::raw::from_buf(name as *const u8);
+        let (ptr, len) = match self.entities.find(&key) {
+            Some(entity) => (entity.ptr, entity.content.len()),
+            None if self.external_entities && self.needs_document() => {
+                return Ok(ffi::xmlSAX2GetEntity(self.ctxt, name));
+            }
+            None => return Ok(null()),
+        };
+        if ptr.is_null() {
+            return Err(ExternalEntity(key));
+        }
+        if !self.expand_entities {
+            return Ok(ptr);
+        }
+        if self.entity_depth(key.as_slice(), &mut Vec::new()) > self.max_entity_depth {
+            return Err(EntityDepth(key));
+        }
+        // every nested reference is looked up again each time it is
+        // expanded, so this counts all of the text produced
+        self.expanded += len;
+        let consumed = cmp::max(ffi::xmlByteConsumed(self.ctxt), 0) as uint;
+        if self.expanded > ENTITY_EXPANSION_ALLOWANCE
+                && self.expanded > consumed * self.max_entity_ratio {
+            return Err(EntityExpansion);
+        }
+        Ok(ptr)
+    }
+
+    /// How deeply references nest within the named entity, counting the
+    /// entity itself. `open` holds the entities currently being measured.
+    fn entity_depth(&self, name: &str, open: &mut Vec<String>) -> uint {
+        let entity = match self.entities.find(&name.to_string()) {
+            Some(entity) => entity,
+            None => return 0,
+        };
+        match entity.depth.get() {
+            Some(depth) => return depth,
+            None => {}
+        }
+        // a reference loop is reported by libxml2 itself
+        if open.iter().any(|n| name == n.as_slice()) { return 0 }
+        open.push(name.to_string());
+        let depth = 1 + references(entity.content.as_slice()).iter()
+            .map(|&r| self.entity_depth(r, open))
+            .max().unwrap_or(0);
+        open.pop();
+        entity.depth.set(Some(depth));
+        depth
+    }
+
+    /// Reports a document that broke the limits of the configuration, and
+    /// stops the parse.
+    unsafe fn reject(&mut self, violation: Violation) {
+        let (line, column) = self.position().map_or((0, 0), |p| (p.line, p.column));
+        self.error(ErrorData::from_violation(violation, line, column));
+        self.stop();
+    }
+
+    /// Stops the parse, so that libxml2 returns as soon as it can. Any
+    /// callbacks made in the meantime are ignored.
+    unsafe fn stop(&mut self) {
+        self.stopped = true;
+        if !self.ctxt.is_null() {
+            ffi::xmlStopParser(self.ctxt);
+        }
+    }
+}
+
+/// The kind of an event, as it appears in an `EventMask`.
+fn event_kind(event: &ParseEventRef) -> EventMask {
+    match *event {
+        borrowed::StartDocument | borrowed::EndDocument => DocumentEvents,
+        borrowed::StartElement(..)
+            | borrowed::StartElementNs(..)
+            | borrowed::StartElementAtom(..) => StartElementEvents,
+        borrowed::EndElement(..)
+            | borrowed::EndElementNs(..)
+            | borrowed::EndElementAtom(..) => EndElementEvents,
+        borrowed::Characters(..) => CharacterEvents,
+        borrowed::IgnorableWhitespace(..) => WhitespaceEvents,
+        borrowed::EntityReference(..) => EntityReferenceEvents,
+        borrowed::Comment(..) => CommentEvents,
+        borrowed::CdataBlock(..) => CdataEvents,
+        borrowed::ProcessingInstruction(..) => ProcessingInstructionEvents,
+        borrowed::Dtd(..) => DtdEvents,
+    }
+}
+
+/// The names of the general entities referenced in some replacement text.
+fn references<'a>(text: &'a str) -> Vec<&'a str> {
+    text.split('&').skip(1)
+        .filter_map(|s| s.find(';').map(|end| s.slice_to(end)))
+        .filter(|name| !name.starts_with("#"))
+        .collect()
+}
+
+/// Frees a parser context along with any document that libxml2 built for
+/// loading the DTD.
+unsafe fn free_parser(ctxt: ffi::xmlParserCtxtPtr) {
+    if !(*ctxt).myDoc.is_null() {
+        ffi::xmlFreeDoc((*ctxt).myDoc);
+    }
+    ffi::xmlFreeParserCtxt(ctxt);
+}
+
+#[unsafe_destructor]
+impl<'a> Drop for Context<'a> {
+    fn drop(&mut self) {
+        for (_, entity) in self.entities.iter() {
+            if !entity.ptr.is_null() {
+                unsafe { ffi::xmlFreeNode(entity.ptr) };
+            }
+        }
+    }
+}
+
+pub fn new_handler(config: &ParserConfig) -> ffi::xmlSAXHandler {
+    let mut handler = ffi::xmlSAXHandler {
+        internalSubset:         Some(internal_subset),
         isStandalone:           None,
         hasInternalSubset:      None,
         hasExternalSubset:      None,
         resolveEntity:          None,
-        getEntity:              None,
-        entityDecl:             None,
-        notationDecl:           None,
-        attributeDecl:          None,
-        elementDecl:            None,
-        unparsedEntityDecl:     None,
+        getEntity:              Some(get_entity),
+        entityDecl:             Some(entity_decl),
+        notationDecl:           Some(notation_decl),
+        attributeDecl:          Some(attribute_decl),
+        elementDecl:            Some(element_decl),
+        unparsedEntityDecl:     Some(unparsed_entity_decl),
         setDocumentLocator:     None,
         startDocument:          Some(start_document),
         endDocument:            Some(end_document),
@@ -44,86 +464,402 @@ pub fn new_handler() -> ffi::xmlSAXHandler {
         endElement:             Some(end_element),
         reference:              None,
         characters:             Some(characters),
-        ignorableWhitespace:    None,               // use characters
-        processingInstruction:  None,
+        ignorableWhitespace:    Some(characters),
+        processingInstruction:  Some(processing_instruction),
         comment:                Some(comment),
         warning:                None,               // use serror
         error:                  None,               // use serror
         fatalError:             None,               // use serror
         getParameterEntity:     None,
         cdataBlock:             Some(cdata_block),
-        externalSubset:         None,
+        externalSubset:         Some(external_subset),
         initialized:            ffi::XML_SAX2_MAGIC,
         _private:               null(),
         startElementNs:         None,
         endElementNs:           None,
         serror:                 Some(serror),
+    };
+    // libxml2 only switches to SAX2 element callbacks when `startElementNs`
+    // or `endElementNs` is set
+    if config.namespaces {
+        handler.startElement    = None;
+        handler.endElement      = None;
+        handler.startElementNs  = Some(start_element_ns);
+        handler.endElementNs    = Some(end_element_ns);
+    }
+    if !config.expand_entities {
+        handler.reference       = Some(reference);
+    }
+    if config.options & (ffi::XML_PARSE_DTDLOAD | ffi::XML_PARSE_DTDVALID) != 0 {
+        handler.resolveEntity   = Some(resolve_entity);
+    }
+    // whitespace-only text from `characters` is sorted out by the context
+    match config.whitespace {
+        PreserveWhitespace => {}
+        StripWhitespace     => handler.ignorableWhitespace = None,
+        ReportWhitespace    => handler.ignorableWhitespace = Some(ignorable_whitespace),
+    }
+    unbind_masked(&mut handler, config);
+    handler
+}
+
+/// Leaves out the callbacks that would only report events that have been
+/// masked out. The rest are still needed by the context, or change what
+/// libxml2 does when they are missing, and are filtered by `dispatch`
+/// instead.
+fn unbind_masked(handler: &mut ffi::xmlSAXHandler, config: &ParserConfig) {
+    let events = config.events;
+    // text that is held back is only sent once the markup that ends it is
+    // seen, so the callbacks for markup are kept even if it is masked out
+    let holds_text = config.whitespace != PreserveWhitespace || config.coalesce != SplitText;
+    if !events.contains(StartElementEvents) && !holds_text {
+        handler.startElement    = None;
+        handler.startElementNs  = None;
+    }
+    if !events.contains(EndElementEvents) && !holds_text {
+        handler.endElementNs    = None;
+        // with no SAX1 element callbacks at all, libxml2 switches to SAX2
+        if handler.startElement.is_some() || config.namespaces {
+            handler.endElement  = None;
+        }
+    }
+    let whitespace = config.whitespace == ReportWhitespace && events.contains(WhitespaceEvents);
+    if !events.contains(CharacterEvents) && !whitespace {
+        handler.characters          = None;
+        handler.ignorableWhitespace = None;
+    }
+    if !whitespace && config.whitespace == ReportWhitespace {
+        handler.ignorableWhitespace = None;
+    }
+    if !events.contains(DocumentEvents) {
+        handler.endDocument     = None;
+    }
+    if !events.contains(CommentEvents) && !holds_text {
+        handler.comment         = None;
+    }
+    if !events.contains(ProcessingInstructionEvents) && !holds_text {
+        handler.processingInstruction = None;
+    }
+    if !events.contains(DtdEvents) {
+        handler.elementDecl         = None;
+        handler.attributeDecl       = None;
+        handler.notationDecl        = None;
+        handler.unparsedEntityDecl  = None;
     }
 }
 
 // aaaaaahhh this is so unsafe!
-unsafe fn sender_from_ptr<'a>(ctx: *const c_void) -> &'a Sender<ParseResult> { transmute(ctx) }
+unsafe fn context_from_ptr<'a>(ctx: *const c_void) -> &'a mut Context<'a> { transmute(ctx) }
 
 extern "C" fn start_document(ctx: *const c_void) {
     unsafe {
-        sender_from_ptr(ctx).send(
-            Ok(StartDocument)
-        );
+        let context = context_from_ptr(ctx);
+        context.dispatch(borrowed::StartDocument);
+        // libxml2 will only load the external subset into a document
+        if context.needs_document() {
+            ffi::xmlSAX2StartDocument(context.ctxt);
+        }
     }
 }
 
 extern "C" fn end_document(ctx: *const c_void) {
     unsafe {
-        sender_from_ptr(ctx).send(
-            Ok(EndDocument)
-        );
+        context_from_ptr(ctx).dispatch(borrowed::EndDocument);
     }
 }
 
 extern "C" fn start_element(ctx: *const c_void, name: *const ffi::xmlChar, atts: *const *const ffi::xmlChar) {
     unsafe {
-        sender_from_ptr(ctx).send(
-            Ok(StartElement(string::raw::from_buf(name as *const u8), Attributes::from_buf(atts)))
-        );
+        let context = context_from_ptr(ctx);
+        let dict = context.dict;
+        if dict.is_null() {
+            context.dispatch(borrowed::StartElement(
+                str_from_buf(name), AttributesRef::from_buf(atts)
+            ));
+        } else {
+            context.dispatch(borrowed::StartElementAtom(
+                AtomQName::from_buf(dict, name, null(), null()),
+                AttributesRef::from_buf(atts).interned_in(dict),
+                NamespacesRef::from_buf(0, null())
+            ));
+        }
     }
 }
 
 extern "C" fn end_element(ctx: *const c_void, name: *const ffi::xmlChar) {
     unsafe {
-        sender_from_ptr(ctx).send(
-            Ok(EndElement(string::raw::from_buf(name as *const u8)))
-        );
+        let context = context_from_ptr(ctx);
+        let dict = context.dict;
+        if dict.is_null() {
+            context.dispatch(borrowed::EndElement(str_from_buf(name)));
+        } else {
+            context.dispatch(borrowed::EndElementAtom(
+                AtomQName::from_buf(dict, name, null(), null())
+            ));
+        }
+    }
+}
+
+extern "C" fn start_element_ns(ctx: *const c_void, localname: *const ffi::xmlChar,
+                               prefix: *const ffi::xmlChar, uri: *const ffi::xmlChar,
+                               nb_namespaces: c_int, namespaces: *const *const ffi::xmlChar,
+                               nb_attributes: c_int, _nb_defaulted: c_int,
+                               attributes: *const *const ffi::xmlChar) {
+    unsafe {
+        let context = context_from_ptr(ctx);
+        let dict = context.dict;
+        if dict.is_null() {
+            context.dispatch(borrowed::StartElementNs(
+                QNameRef::from_buf(localname, prefix, uri),
+                AttributesRef::from_ns_buf(nb_attributes, attributes),
+                NamespacesRef::from_buf(nb_namespaces, namespaces)
+            ));
+        } else {
+            context.dispatch(borrowed::StartElementAtom(
+                AtomQName::from_buf(dict, localname, prefix, uri),
+                AttributesRef::from_ns_buf(nb_attributes, attributes).interned_in(dict),
+                NamespacesRef::from_buf(nb_namespaces, namespaces)
+            ));
+        }
+    }
+}
+
+extern "C" fn end_element_ns(ctx: *const c_void, localname: *const ffi::xmlChar,
+                             prefix: *const ffi::xmlChar, uri: *const ffi::xmlChar) {
+    unsafe {
+        let context = context_from_ptr(ctx);
+        let dict = context.dict;
+        if dict.is_null() {
+            context.dispatch(borrowed::EndElementNs(
+                QNameRef::from_buf(localname, prefix, uri)
+            ));
+        } else {
+            context.dispatch(borrowed::EndElementAtom(
+                AtomQName::from_buf(dict, localname, prefix, uri)
+            ));
+        }
     }
 }
 
 extern "C" fn characters(ctx: *const c_void, ch: *const ffi::xmlChar, len: c_int) {
     unsafe {
-        sender_from_ptr(ctx).send(
-            Ok(Characters(string::raw::from_buf_len(ch, len as uint)))
-        );
+        context_from_ptr(ctx).text(CharacterText, str_from_buf_len(ch, len as uint));
+    }
+}
+
+/// Checks for the characters matched by the `S` production of the XML spec.
+fn is_whitespace(ch: &str) -> bool {
+    ch.bytes().all(|b| b == b' ' || b == b'\t' || b == b'\r' || b == b'\n')
+}
+
+extern "C" fn ignorable_whitespace(ctx: *const c_void, ch: *const ffi::xmlChar, len: c_int) {
+    unsafe {
+        context_from_ptr(ctx).dispatch(borrowed::IgnorableWhitespace(
+            str_from_buf_len(ch, len as uint)
+        ));
+    }
+}
+
+extern "C" fn internal_subset(ctx: *const c_void, name: *const ffi::xmlChar,
+                              external_id: *const ffi::xmlChar, system_id: *const ffi::xmlChar) {
+    unsafe {
+        let context = context_from_ptr(ctx);
+        // the HTML parser reports `<!DOCTYPE>` without a name
+        if context.wants(DtdEvents) {
+            context.dispatch(borrowed::Dtd(
+                &dtd::DoctypeDecl(from_buf_opt(name).unwrap_or(String::new()),
+                                  dtd::ExternalId::from_buf(external_id, system_id))
+            ));
+        }
+        if context.needs_document() {
+            ffi::xmlSAX2InternalSubset(context.ctxt, name, external_id, system_id);
+        }
+    }
+}
+
+extern "C" fn external_subset(ctx: *const c_void, name: *const ffi::xmlChar,
+                              external_id: *const ffi::xmlChar, system_id: *const ffi::xmlChar) {
+    // libxml2 notifies us even when there is no external subset
+    if external_id.is_null() && system_id.is_null() { return }
+    unsafe {
+        let context = context_from_ptr(ctx);
+        if context.wants(DtdEvents) {
+            context.dispatch(borrowed::Dtd(
+                &dtd::ExternalSubset(from_buf_opt(name).unwrap_or(String::new()),
+                                     dtd::ExternalId::from_buf(external_id, system_id))
+            ));
+        }
+        if context.load_dtd && !context.ctxt.is_null() {
+            ffi::xmlSAX2ExternalSubset(context.ctxt, name, external_id, system_id);
+        }
+    }
+}
+
+extern "C" fn resolve_entity(ctx: *const c_void, public_id: *const ffi::xmlChar,
+                             system_id: *const ffi::xmlChar) -> *const c_void {
+    unsafe {
+        let context = context_from_ptr(ctx);
+        if context.ctxt.is_null() { return null() }
+        ffi::xmlSAX2ResolveEntity(context.ctxt, public_id, system_id)
+    }
+}
+
+extern "C" fn element_decl(ctx: *const c_void, name: *const ffi::xmlChar, ty: c_int,
+                           content: *const ffi::xmlElementContent) {
+    unsafe {
+        context_from_ptr(ctx).dispatch(borrowed::Dtd(
+            &dtd::ElementDecl(string::raw::from_buf(name as *const u8),
+                              dtd::ContentSpec::from_ptr(ty, content))
+        ));
+    }
+}
+
+extern "C" fn attribute_decl(ctx: *const c_void, elem: *const ffi::xmlChar,
+                             fullname: *const ffi::xmlChar, ty: c_int, def: c_int,
+                             default_value: *const ffi::xmlChar,
+                             tree: *const ffi::xmlEnumeration) {
+    unsafe {
+        context_from_ptr(ctx).dispatch(borrowed::Dtd(
+            &dtd::AttributeDecl(dtd::AttributeDef::from_buf(elem, fullname, ty, def,
+                                                            default_value, tree))
+        ));
+        // the enumeration is owned by the callback
+        if !tree.is_null() {
+            ffi::xmlFreeEnumeration(tree);
+        }
+    }
+}
+
+extern "C" fn notation_decl(ctx: *const c_void, name: *const ffi::xmlChar,
+                            public_id: *const ffi::xmlChar, system_id: *const ffi::xmlChar) {
+    unsafe {
+        context_from_ptr(ctx).dispatch(borrowed::Dtd(
+            &dtd::NotationDecl(string::raw::from_buf(name as *const u8),
+                               dtd::ExternalId::from_buf(public_id, system_id))
+        ));
+    }
+}
+
+extern "C" fn unparsed_entity_decl(ctx: *const c_void, name: *const ffi::xmlChar,
+                                   public_id: *const ffi::xmlChar, system_id: *const ffi::xmlChar,
+                                   notation_name: *const ffi::xmlChar) {
+    unsafe {
+        context_from_ptr(ctx).dispatch(borrowed::Dtd(
+            &dtd::UnparsedEntityDecl(string::raw::from_buf(name as *const u8),
+                                     dtd::ExternalId::from_buf(public_id, system_id),
+                                     string::raw::from_buf(notation_name as *const u8))
+        ));
+    }
+}
+
+extern "C" fn entity_decl(ctx: *const c_void, name: *const ffi::xmlChar, ty: c_int,
+                          public_id: *const ffi::xmlChar, system_id: *const ffi::xmlChar,
+                          content: *const ffi::xmlChar) {
+    unsafe {
+        let context = context_from_ptr(ctx);
+        if context.wants(DtdEvents) {
+            context.dispatch(borrowed::Dtd(
+                &dtd::EntityDecl(dtd::EntityDef::from_buf(name, ty, public_id, system_id, content))
+            ));
+        }
+        let key = string::raw::from_buf(name as *const u8);
+        // only the first declaration of an entity is binding
+        if context.entities.contains_key(&key) {
+            return;
+        }
+        match ty {
+            ffi::XML_INTERNAL_GENERAL_ENTITY => {}
+            ffi::XML_EXTERNAL_GENERAL_PARSED_ENTITY if context.external_entities
+                                                        && context.needs_document() => {
+                // libxml2 loads these itself, from the document it builds
+                ffi::xmlSAX2EntityDecl(context.ctxt, name, ty, public_id, system_id, content);
+                return;
+            }
+            ffi::XML_EXTERNAL_GENERAL_PARSED_ENTITY if context.expand_entities => {
+                // remembered so that references to it can be refused
+                context.entities.insert(key, Entity {
+                    ptr: null(),
+                    content: String::new(),
+                    depth: Cell::new(None),
+                });
+                return;
+            }
+            // an unexpanded external entity is reported like any other
+            ffi::XML_EXTERNAL_GENERAL_PARSED_ENTITY => {}
+            _ => return,
+        }
+        // an empty replacement text means libxml2 has nothing to expand, and
+        // falls back to calling `reference`
+        let replacement = if context.expand_entities { content } else { b"\0".as_ptr() };
+        let entity = ffi::xmlNewEntity(null(), name, ffi::XML_INTERNAL_GENERAL_ENTITY,
+                                       null(), null(), replacement);
+        context.entities.insert(key, Entity {
+            ptr: entity,
+            content: from_buf_opt(content).unwrap_or(String::new()),
+            depth: Cell::new(None),
+        });
+    }
+}
+
+extern "C" fn get_entity(ctx: *const c_void, name: *const ffi::xmlChar) -> ffi::xmlEntityPtr {
+    unsafe {
+        let context = context_from_ptr(ctx);
+        if context.stopped { return null() }
+        match context.entity(name) {
+            Ok(entity) => entity,
+            Err(violation) => {
+                context.reject(violation);
+                null()
+            }
+        }
+    }
+}
+
+extern "C" fn reference(ctx: *const c_void, name: *const ffi::xmlChar) {
+    unsafe {
+        context_from_ptr(ctx).dispatch(borrowed::EntityReference(str_from_buf(name)));
     }
 }
 
 extern "C" fn comment(ctx: *const c_void, value: *const ffi::xmlChar) {
     unsafe {
-        sender_from_ptr(ctx).send(
-            Ok(Comment(string::raw::from_buf(value as *const u8)))
-        );
+        context_from_ptr(ctx).dispatch(borrowed::Comment(str_from_buf(value)));
     }
 }
 
 extern "C" fn cdata_block(ctx: *const c_void, value: *const ffi::xmlChar, len: c_int) {
     unsafe {
-        sender_from_ptr(ctx).send(
-            Ok(CdataBlock(string::raw::from_buf_len(value, len as uint)))
-        );
+        context_from_ptr(ctx).text(CdataText, str_from_buf_len(value, len as uint));
+    }
+}
+
+extern "C" fn processing_instruction(ctx: *const c_void, target: *const ffi::xmlChar, data: *const ffi::xmlChar) {
+    unsafe {
+        context_from_ptr(ctx).dispatch(borrowed::ProcessingInstruction(
+            str_from_buf(target), str_from_buf_opt(data)
+        ));
     }
 }
 
 extern "C" fn serror(ctx: *const c_void, error: *const ffi::xmlError) {
     unsafe {
         ErrorData::from_ptr(error).map(|err| {
-            sender_from_ptr(ctx).send(Err(err.clone()));
+            context_from_ptr(ctx).error(err);
         });
     }
 }
+
+/// Fills libxml2's input buffer from a `&mut Reader`, returning the number of
+/// bytes read, `0` at the end of the input, or `-1` on an error.
+pub extern "C" fn read_reader(ctx: *const c_void, buffer: *mut c_char, len: c_int) -> c_int {
+    unsafe {
+        let reader: &mut &mut Reader = transmute(ctx);
+        slice::raw::mut_buf_as_slice(buffer as *mut u8, len as uint, |buf| {
+            match reader.read(buf) {
+                Ok(n) => n as c_int,
+                Err(ref err) if err.kind == io::EndOfFile => 0,
+                Err(_) => -1,
+            }
+        })
+    }
+}
@@ -18,7 +18,7 @@
 
 #![allow(non_camel_case_types)]
 
-use libc::{c_char, c_int, c_uchar, c_uint, c_void};
+use libc::{c_char, c_int, c_long, c_uchar, c_uint, c_ulong, c_void};
 
 pub type xmlChar = c_uchar;
 
@@ -32,8 +32,8 @@ pub type getEntitySAXFunc               = extern "C" fn(ctx: *const c_void, name
 pub type getParameterEntitySAXFunc      = extern "C" fn(ctx: *const c_void, name: *const xmlChar) -> *const c_void /*xmlEntityPtr*/;
 pub type entityDeclSAXFunc              = extern "C" fn(ctx: *const c_void, name: *const xmlChar, ty: c_int, publicId: *const xmlChar, systemId: *const xmlChar, content: *const xmlChar);
 pub type notationDeclSAXFunc            = extern "C" fn(ctx: *const c_void, name: *const xmlChar, publicId: *const xmlChar, systemId: *const xmlChar);
-pub type attributeDeclSAXFunc           = extern "C" fn(ctx: *const c_void, elem: *const xmlChar, fullname: *const xmlChar, ty: c_int, def: c_int, defaultValue: *const xmlChar, tree: *const c_void /*xmlEnumerationPtr*/);
-pub type elementDeclSAXFunc             = extern "C" fn(ctx: *const c_void, name: *const xmlChar, ty: c_int, content: *const c_void /*xmlElementContentPtr*/);
+pub type attributeDeclSAXFunc           = extern "C" fn(ctx: *const c_void, elem: *const xmlChar, fullname: *const xmlChar, ty: c_int, def: c_int, defaultValue: *const xmlChar, tree: *const xmlEnumeration);
+pub type elementDeclSAXFunc             = extern "C" fn(ctx: *const c_void, name: *const xmlChar, ty: c_int, content: *const xmlElementContent);
 pub type unparsedEntityDeclSAXFunc      = extern "C" fn(ctx: *const c_void, name: *const xmlChar, publicId: *const xmlChar, systemId: *const xmlChar, notationName: *const xmlChar);
 pub type setDocumentLocatorSAXFunc      = extern "C" fn(ctx: *const c_void, loc: *const c_void /*xmlSAXLocatorPtr*/);
 pub type startDocumentSAXFunc           = extern "C" fn(ctx: *const c_void);
@@ -134,6 +134,286 @@ pub struct xmlError {
 
 pub type xmlErrorPtr = *const xmlError;
 
+/// Error code type alias, the values of `xmlParserErrors`
+pub type xmlParserErrors = c_int;
+
+/// Attempt to load a network resource while it is forbidden
+pub static XML_IO_NETWORK_ATTEMPT: xmlParserErrors = 1543;
+
+pub type xmlDocPtr = *const c_void;
+
+/// The leading fields of the parser context, as laid out by libxml2 2.9. The
+/// rest of the struct is private to libxml2, so it must only ever be handled
+/// through a pointer.
+///
+/// Only `dict` is read, for `intern_names`, and only `myDoc` when the parser
+/// built a document for the DTD. This layout is checked against the 2.9
+/// headers, which are the only ones supported; check `parser.h` before
+/// moving to another release.
+pub struct xmlParserCtxt {
+    /// The SAX handler
+    pub sax: *const xmlSAXHandler,
+    /// For SAX interface only, used by DOM build
+    pub userData: *const c_void,
+    /// The document being built
+    pub myDoc: xmlDocPtr,
+    /// Is the document well formed
+    pub wellFormed: c_int,
+    /// Shall we replace entities?
+    pub replaceEntities: c_int,
+    /// The XML version string
+    pub version: *const xmlChar,
+    /// The declared encoding, if any
+    pub encoding: *const xmlChar,
+    /// The standalone document declaration
+    pub standalone: c_int,
+    /// Set for the HTML parser
+    pub html: c_int,
+    /// The current input stream
+    pub input: *const c_void,
+    pub inputNr: c_int,
+    pub inputMax: c_int,
+    pub inputTab: *const c_void,
+    /// The current node, when building a tree
+    pub node: *const c_void,
+    pub nodeNr: c_int,
+    pub nodeMax: c_int,
+    pub nodeTab: *const c_void,
+    /// Whether node info should be kept
+    pub record_info: c_int,
+    pub node_seq: xmlParserNodeInfoSeq,
+    /// The last error number
+    pub errNo: c_int,
+    pub hasExternalSubset: c_int,
+    pub hasPErefs: c_int,
+    /// Set while parsing an external entity
+    pub external: c_int,
+    /// Is the document valid
+    pub valid: c_int,
+    /// Shall we try to validate?
+    pub validate: c_int,
+    pub vctxt: xmlValidCtxt,
+    /// The current state of the push parser
+    pub instate: xmlParserInputState,
+    pub token: c_int,
+    pub directory: *const c_char,
+    /// The current element name
+    pub name: *const xmlChar,
+    pub nameNr: c_int,
+    pub nameMax: c_int,
+    pub nameTab: *const *const xmlChar,
+    pub nbChars: c_long,
+    pub checkIndex: c_long,
+    /// Whether blank text nodes are kept
+    pub keepBlanks: c_int,
+    /// Set once the SAX callbacks have been disabled
+    pub disableSAX: c_int,
+    /// Set while parsing the internal or external subset
+    pub inSubset: c_int,
+    /// The name of the internal subset
+    pub intSubName: *const xmlChar,
+    /// The URI of the external subset
+    pub extSubURI: *const xmlChar,
+    /// The system ID of the external subset
+    pub extSubSystem: *const xmlChar,
+    /// Whether `xml:space` preserves whitespace
+    pub space: *const c_int,
+    pub spaceNr: c_int,
+    pub spaceMax: c_int,
+    pub spaceTab: *const c_int,
+    /// The depth of entity nesting
+    pub depth: c_int,
+    pub entity: *const c_void,
+    pub charset: c_int,
+    pub nodelen: c_int,
+    pub nodemem: c_int,
+    pub pedantic: c_int,
+    pub _private: *const c_void,
+    /// Whether the external subset should be loaded
+    pub loadsubset: c_int,
+    pub linenumbers: c_int,
+    pub catalogs: *const c_void,
+    pub recovery: c_int,
+    pub progressive: c_int,
+    /// The dictionary that the parser interns names in
+    pub dict: xmlDictPtr,
+}
+
+/// Node info recorded by the parser, embedded in `xmlParserCtxt`.
+pub struct xmlParserNodeInfoSeq {
+    pub maximum: c_ulong,
+    pub length: c_ulong,
+    pub buffer: *const c_void,
+}
+
+/// The validation context, embedded in `xmlParserCtxt`.
+pub struct xmlValidCtxt {
+    pub userData: *const c_void,
+    pub error: *const c_void,
+    pub warning: *const c_void,
+    pub node: *const c_void,
+    pub nodeNr: c_int,
+    pub nodeMax: c_int,
+    pub nodeTab: *const c_void,
+    pub finishDtd: c_uint,
+    pub doc: xmlDocPtr,
+    pub valid: c_int,
+    pub vstate: *const c_void,
+    pub vstateNr: c_int,
+    pub vstateMax: c_int,
+    pub vstateTab: *const c_void,
+    pub am: *const c_void,
+    pub state: *const c_void,
+}
+
+/// The state of the push parser
+pub type xmlParserInputState = c_int;
+
+pub type xmlParserCtxtPtr = *const xmlParserCtxt;
+
+/// A string dictionary, private to libxml2
+pub type xmlDictPtr = *const c_void;
+
+pub type xmlInputReadCallback           = extern "C" fn(context: *const c_void, buffer: *mut c_char, len: c_int) -> c_int;
+pub type xmlInputCloseCallback          = extern "C" fn(context: *const c_void) -> c_int;
+
+pub type xmlEntityPtr = *const c_void;
+
+/// Entity type alias
+pub type xmlEntityType = c_int;
+
+pub static XML_INTERNAL_GENERAL_ENTITY:          xmlEntityType = 1;
+pub static XML_EXTERNAL_GENERAL_PARSED_ENTITY:   xmlEntityType = 2;
+pub static XML_EXTERNAL_GENERAL_UNPARSED_ENTITY: xmlEntityType = 3;
+pub static XML_INTERNAL_PARAMETER_ENTITY:        xmlEntityType = 4;
+pub static XML_EXTERNAL_PARAMETER_ENTITY:        xmlEntityType = 5;
+pub static XML_INTERNAL_PREDEFINED_ENTITY:       xmlEntityType = 6;
+
+/// Element type alias, from the `libxml/tree.h` header
+pub type xmlElementTypeVal = c_int;
+
+pub static XML_ELEMENT_TYPE_UNDEFINED:  xmlElementTypeVal = 0;
+pub static XML_ELEMENT_TYPE_EMPTY:      xmlElementTypeVal = 1;
+pub static XML_ELEMENT_TYPE_ANY:        xmlElementTypeVal = 2;
+pub static XML_ELEMENT_TYPE_MIXED:      xmlElementTypeVal = 3;
+pub static XML_ELEMENT_TYPE_ELEMENT:    xmlElementTypeVal = 4;
+
+/// Element content type alias
+pub type xmlElementContentType = c_int;
+
+pub static XML_ELEMENT_CONTENT_PCDATA:  xmlElementContentType = 1;
+pub static XML_ELEMENT_CONTENT_ELEMENT: xmlElementContentType = 2;
+pub static XML_ELEMENT_CONTENT_SEQ:     xmlElementContentType = 3;
+pub static XML_ELEMENT_CONTENT_OR:      xmlElementContentType = 4;
+
+/// Element content occurrence type alias
+pub type xmlElementContentOccur = c_int;
+
+pub static XML_ELEMENT_CONTENT_ONCE:    xmlElementContentOccur = 1;
+pub static XML_ELEMENT_CONTENT_OPT:     xmlElementContentOccur = 2;
+pub static XML_ELEMENT_CONTENT_MULT:    xmlElementContentOccur = 3;
+pub static XML_ELEMENT_CONTENT_PLUS:    xmlElementContentOccur = 4;
+
+/// A node of an element content model
+pub struct xmlElementContent {
+    /// PCDATA, ELEMENT, SEQ or OR
+    pub ty: xmlElementContentType,
+    /// ONCE, OPT, MULT or PLUS
+    pub ocur: xmlElementContentOccur,
+    /// Element name
+    pub name: *const xmlChar,
+    /// First child
+    pub c1: *const xmlElementContent,
+    /// Second child
+    pub c2: *const xmlElementContent,
+    /// Parent
+    pub parent: *const xmlElementContent,
+    /// Namespace prefix
+    pub prefix: *const xmlChar,
+}
+
+/// Attribute type alias
+pub type xmlAttributeType = c_int;
+
+pub static XML_ATTRIBUTE_CDATA:         xmlAttributeType = 1;
+pub static XML_ATTRIBUTE_ID:            xmlAttributeType = 2;
+pub static XML_ATTRIBUTE_IDREF:         xmlAttributeType = 3;
+pub static XML_ATTRIBUTE_IDREFS:        xmlAttributeType = 4;
+pub static XML_ATTRIBUTE_ENTITY:        xmlAttributeType = 5;
+pub static XML_ATTRIBUTE_ENTITIES:      xmlAttributeType = 6;
+pub static XML_ATTRIBUTE_NMTOKEN:       xmlAttributeType = 7;
+pub static XML_ATTRIBUTE_NMTOKENS:      xmlAttributeType = 8;
+pub static XML_ATTRIBUTE_ENUMERATION:   xmlAttributeType = 9;
+pub static XML_ATTRIBUTE_NOTATION:      xmlAttributeType = 10;
+
+/// Attribute default type alias
+pub type xmlAttributeDefault = c_int;
+
+pub static XML_ATTRIBUTE_NONE:          xmlAttributeDefault = 1;
+pub static XML_ATTRIBUTE_REQUIRED:      xmlAttributeDefault = 2;
+pub static XML_ATTRIBUTE_IMPLIED:       xmlAttributeDefault = 3;
+pub static XML_ATTRIBUTE_FIXED:         xmlAttributeDefault = 4;
+
+/// A list of the values allowed by an enumerated attribute
+pub struct xmlEnumeration {
+    /// Next one
+    pub next: *const xmlEnumeration,
+    /// Enumeration name
+    pub name: *const xmlChar,
+}
+
+/// Parser option type alias
+pub type xmlParserOption = c_int;
+
+/// Recover on errors
+pub static XML_PARSE_RECOVER:   xmlParserOption = 1 << 0;
+/// Substitute entities
+pub static XML_PARSE_NOENT:     xmlParserOption = 1 << 1;
+/// Load the external subset
+pub static XML_PARSE_DTDLOAD:   xmlParserOption = 1 << 2;
+/// Default DTD attributes
+pub static XML_PARSE_DTDATTR:   xmlParserOption = 1 << 3;
+/// Validate with the DTD
+pub static XML_PARSE_DTDVALID:  xmlParserOption = 1 << 4;
+/// Remove blank nodes
+pub static XML_PARSE_NOBLANKS:  xmlParserOption = 1 << 8;
+/// Forbid network access
+pub static XML_PARSE_NONET:     xmlParserOption = 1 << 11;
+/// Remove redundant namespaces declarations
+pub static XML_PARSE_NSCLEAN:   xmlParserOption = 1 << 13;
+/// Merge CDATA as text nodes
+pub static XML_PARSE_NOCDATA:   xmlParserOption = 1 << 14;
+/// Relax any hardcoded limit from the parser
+pub static XML_PARSE_HUGE:      xmlParserOption = 1 << 19;
+/// Ignore the internal document encoding hint
+pub static XML_PARSE_IGNORE_ENC: xmlParserOption = 1 << 21;
+
+/// HTML parser option type alias, from `libxml/HTMLparser.h`
+pub type htmlParserOption = c_int;
+
+/// Relaxed parsing
+pub static HTML_PARSE_RECOVER:  htmlParserOption = 1 << 0;
+/// Remove blank nodes
+pub static HTML_PARSE_NOBLANKS: htmlParserOption = 1 << 8;
+/// Forbid network access
+pub static HTML_PARSE_NONET:    htmlParserOption = 1 << 11;
+
+/// Character encoding type alias
+pub type xmlCharEncoding = c_int;
+
+/// Detect the encoding from the document itself
+pub static XML_CHAR_ENCODING_NONE:      xmlCharEncoding = 0;
+/// UTF-8
+pub static XML_CHAR_ENCODING_UTF8:      xmlCharEncoding = 1;
+/// UTF-16 little endian
+pub static XML_CHAR_ENCODING_UTF16LE:   xmlCharEncoding = 2;
+/// UTF-16 big endian
+pub static XML_CHAR_ENCODING_UTF16BE:   xmlCharEncoding = 3;
+/// ISO-8859-1 ISO Latin 1
+pub static XML_CHAR_ENCODING_8859_1:    xmlCharEncoding = 10;
+/// pure ASCII
+pub static XML_CHAR_ENCODING_ASCII:     xmlCharEncoding = 22;
+
 /// libxml2 function bindings
 #[link(name = "xml2")]
 extern "C" {
@@ -143,4 +423,76 @@ extern "C" {
                                  user_data: *const c_void,
                                  buffer: *const c_char,
                                  size: c_int) -> c_int;
+    pub fn xmlCreatePushParserCtxt(sax: *const xmlSAXHandler,
+                                   user_data: *const c_void,
+                                   chunk: *const c_char,
+                                   size: c_int,
+                                   filename: *const c_char) -> xmlParserCtxtPtr;
+    pub fn xmlParseChunk(ctxt: xmlParserCtxtPtr,
+                         chunk: *const c_char,
+                         size: c_int,
+                         terminate: c_int) -> c_int;
+    pub fn xmlCreateIOParserCtxt(sax: *const xmlSAXHandler,
+                                 user_data: *const c_void,
+                                 ioread: Option<xmlInputReadCallback>,
+                                 ioclose: Option<xmlInputCloseCallback>,
+                                 ioctx: *const c_void,
+                                 enc: xmlCharEncoding) -> xmlParserCtxtPtr;
+    pub fn xmlParseDocument(ctxt: xmlParserCtxtPtr) -> c_int;
+    pub fn xmlSwitchEncoding(ctxt: xmlParserCtxtPtr, enc: xmlCharEncoding) -> c_int;
+    pub fn xmlCtxtUseOptions(ctxt: xmlParserCtxtPtr, options: c_int) -> c_int;
+    pub fn xmlNewEntity(doc: *const c_void,
+                        name: *const xmlChar,
+                        ty: c_int,
+                        ExternalID: *const xmlChar,
+                        SystemID: *const xmlChar,
+                        content: *const xmlChar) -> xmlEntityPtr;
+    pub fn xmlFreeNode(node: *const c_void);
+    pub fn xmlFreeEnumeration(cur: *const xmlEnumeration);
+    pub fn xmlFreeDoc(cur: xmlDocPtr);
+    pub fn xmlSAX2StartDocument(ctx: xmlParserCtxtPtr);
+    pub fn xmlSAX2InternalSubset(ctx: xmlParserCtxtPtr,
+                                 name: *const xmlChar,
+                                 ExternalID: *const xmlChar,
+                                 SystemID: *const xmlChar);
+    pub fn xmlSAX2EntityDecl(ctx: xmlParserCtxtPtr,
+                             name: *const xmlChar,
+                             ty: c_int,
+                             publicId: *const xmlChar,
+                             systemId: *const xmlChar,
+                             content: *const xmlChar);
+    pub fn xmlSAX2GetEntity(ctx: xmlParserCtxtPtr,
+                            name: *const xmlChar) -> xmlEntityPtr;
+    pub fn xmlSAX2ExternalSubset(ctx: xmlParserCtxtPtr,
+                                 name: *const xmlChar,
+                                 ExternalID: *const xmlChar,
+                                 SystemID: *const xmlChar);
+    pub fn xmlSAX2ResolveEntity(ctx: xmlParserCtxtPtr,
+                                publicId: *const xmlChar,
+                                systemId: *const xmlChar) -> *const c_void /*xmlParserInputPtr*/;
+    pub fn xmlSAX2GetLineNumber(ctx: xmlParserCtxtPtr) -> c_int;
+    pub fn xmlSAX2GetColumnNumber(ctx: xmlParserCtxtPtr) -> c_int;
+    pub fn xmlByteConsumed(ctxt: xmlParserCtxtPtr) -> c_long;
+    pub fn xmlStopParser(ctxt: xmlParserCtxtPtr);
+    pub fn xmlCtxtGetLastError(ctx: xmlParserCtxtPtr) -> xmlErrorPtr;
+    pub fn htmlCreatePushParserCtxt(sax: *const xmlSAXHandler,
+                                    user_data: *const c_void,
+                                    chunk: *const c_char,
+                                    size: c_int,
+                                    filename: *const c_char,
+                                    enc: xmlCharEncoding) -> xmlParserCtxtPtr;
+    pub fn htmlParseChunk(ctxt: xmlParserCtxtPtr,
+                          chunk: *const c_char,
+                          size: c_int,
+                          terminate: c_int) -> c_int;
+    pub fn htmlCtxtUseOptions(ctxt: xmlParserCtxtPtr, options: c_int) -> c_int;
+    pub fn xmlFreeParserCtxt(ctxt: xmlParserCtxtPtr);
+    pub fn xmlDictCreate() -> xmlDictPtr;
+    pub fn xmlDictReference(dict: xmlDictPtr) -> c_int;
+    pub fn xmlDictFree(dict: xmlDictPtr);
+    pub fn xmlDictLookup(dict: xmlDictPtr, name: *const xmlChar, len: c_int) -> *const xmlChar;
+    pub fn xmlDictExists(dict: xmlDictPtr, name: *const xmlChar, len: c_int) -> *const xmlChar;
+    pub fn xmlDictOwns(dict: xmlDictPtr, name: *const xmlChar) -> c_int;
+    pub fn xmlDictQLookup(dict: xmlDictPtr, prefix: *const xmlChar, name: *const xmlChar) -> *const xmlChar;
+    pub fn xmlDictSize(dict: xmlDictPtr) -> c_int;
 }
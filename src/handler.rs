@@ -0,0 +1,182 @@
+// Copyright 2014 The SAX-RS Developers. For a full listing of the authors,
+// refer to the AUTHORS file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Callback based event handling
+
+use std::comm::SyncSender;
+
+use super::*;
+use super::borrowed;
+use super::dtd::DtdEvent;
+use super::error::{ErrorData, ErrorReport};
+
+/// Receives parse events directly from the libxml2 callbacks, as they are
+/// produced. Every method has a default implementation that does nothing, so
+/// only the events of interest need to be handled.
+///
+/// # Example
+///
+/// ~~~rust
+/// struct ElementCounter { count: uint }
+///
+/// impl sax::SaxHandler for ElementCounter {
+///     fn start_element(&mut self, _: &str, _: &sax::Attributes) {
+///         self.count += 1;
+///     }
+/// }
+///
+/// let mut counter = ElementCounter { count: 0 };
+/// sax::parse_with_handler(XML_DATA, &mut counter);
+/// ~~~
+pub trait SaxHandler {
+    /// The document has begun to be processed.
+    fn start_document(&mut self) {}
+    /// The document processing has finished.
+    fn end_document(&mut self) {}
+    /// An opening tag has was parsed.
+    fn start_element(&mut self, _name: &str, _atts: &Attributes) {}
+    /// A closing tag was parsed.
+    fn end_element(&mut self, _name: &str) {}
+    /// An opening tag was parsed with namespace processing enabled.
+    fn start_element_ns(&mut self, _name: &QName, _atts: &Attributes, _namespaces: &[Namespace]) {}
+    /// A closing tag was parsed with namespace processing enabled.
+    fn end_element_ns(&mut self, _name: &QName) {}
+    /// An opening tag was parsed with `intern_names` enabled.
+    fn start_element_atom(&mut self, _name: &AtomQName, _atts: &Attributes, _namespaces: &[Namespace]) {}
+    /// A closing tag was parsed with `intern_names` enabled.
+    fn end_element_atom(&mut self, _name: &AtomQName) {}
+    /// Some characters between tags have been recived.
+    fn characters(&mut self, _chars: &str) {}
+    /// Whitespace-only text was received while using `ReportWhitespace`.
+    fn ignorable_whitespace(&mut self, _chars: &str) {}
+    /// An entity reference was left unexpanded.
+    fn entity_reference(&mut self, _name: &str) {}
+    /// A comment tag was parsed.
+    fn comment(&mut self, _value: &str) {}
+    /// A `CDATA` block was parsed.
+    fn cdata_block(&mut self, _value: &str) {}
+    /// A processing instruction was parsed. The data is `None` if the
+    /// instruction only has a target.
+    fn processing_instruction(&mut self, _target: &str, _data: Option<&str>) {}
+    /// A declaration was parsed from the document type definition.
+    fn dtd_event(&mut self, _event: &DtdEvent) {}
+    /// The parser reported a warning or an error.
+    fn error(&mut self, _error: &ErrorData) {}
+
+    /// Receives every event before any of the methods above, with its data
+    /// borrowed from libxml2's buffers. The default implementation calls the
+    /// method that handles the event, which means copying the attributes and
+    /// names of elements. Override this instead to avoid any copying.
+    fn event(&mut self, event: ParseEventRef) {
+        match event {
+            borrowed::StartDocument => self.start_document(),
+            borrowed::EndDocument => self.end_document(),
+            borrowed::StartElement(name, atts) => self.start_element(name, &atts.into_owned()),
+            borrowed::EndElement(name) => self.end_element(name),
+            borrowed::StartElementNs(name, atts, namespaces) => {
+                self.start_element_ns(&name.into_owned(), &atts.into_owned(),
+                                      namespaces.into_owned().as_slice())
+            }
+            borrowed::EndElementNs(name) => self.end_element_ns(&name.into_owned()),
+            borrowed::StartElementAtom(name, atts, namespaces) => {
+                self.start_element_atom(&name, &atts.into_owned(),
+                                        namespaces.into_owned().as_slice())
+            }
+            borrowed::EndElementAtom(name) => self.end_element_atom(&name),
+            borrowed::Characters(ch) => self.characters(ch),
+            borrowed::EntityReference(name) => self.entity_reference(name),
+            borrowed::IgnorableWhitespace(ch) => self.ignorable_whitespace(ch),
+            borrowed::Comment(value) => self.comment(value),
+            borrowed::CdataBlock(value) => self.cdata_block(value),
+            borrowed::ProcessingInstruction(target, data) => {
+                self.processing_instruction(target, data)
+            }
+            borrowed::Dtd(event) => self.dtd_event(event),
+        }
+    }
+
+    /// Called once before the parse begins when `intern_names` is enabled in
+    /// the `ParserConfig`, with the table that the parser interns names in.
+    /// Keep a clone of the table to look up the atoms for names ahead of
+    /// time, so that they can be compared with the ones in events.
+    fn names(&mut self, _names: &Names) {}
+
+    /// Called before each of the other methods when positions are enabled
+    /// in the `ParserConfig`. This is where the parser is when it reports
+    /// the event, which is usually just past the end of the construct.
+    fn set_position(&mut self, _position: Position) {}
+
+    /// Checked before each event is dispatched. Once this returns `true` the
+    /// parse is stopped, and none of the other methods are called again,
+    /// not even `end_document`.
+    fn should_stop(&self) -> bool { false }
+}
+
+/// What a `ChannelHandler` sends over its channel.
+pub enum Message {
+    /// A result, with the position it was reported at
+    EventMessage(ParseResult, Option<Position>),
+    /// The last message, sent once the parse has finished
+    EndMessage(ParseOutcome, ErrorReport),
+}
+
+/// Forwards each event over a channel as a `ParseResult`, along with the
+/// position it was reported at. This is what drives the `Events` returned by
+/// `parse_str` and friends.
+pub struct ChannelHandler {
+    sender: SyncSender<Message>,
+    position: Option<Position>,
+    /// Set once the receiver has hung up
+    hung_up: bool,
+}
+
+impl ChannelHandler {
+    pub fn new(sender: SyncSender<Message>) -> ChannelHandler {
+        ChannelHandler { sender: sender, position: None, hung_up: false }
+    }
+
+    /// Results are sent with `send_opt` because the receiver may have hung
+    /// up, and failing the task here would unwind through libxml2. Instead
+    /// the parse is stopped, since nobody is left to receive the results.
+    fn send(&mut self, result: ParseResult) {
+        if self.sender.send_opt(EventMessage(result, self.position)).is_err() {
+            self.hung_up = true;
+        }
+    }
+
+    /// Sends the outcome and report of the parse, after which the channel is
+    /// closed.
+    pub fn finish(self, outcome: ParseOutcome, report: ErrorReport) {
+        let _ = self.sender.send_opt(EndMessage(outcome, report));
+    }
+}
+
+impl SaxHandler for ChannelHandler {
+    fn event(&mut self, event: ParseEventRef) {
+        self.send(Ok(event.into_owned()));
+    }
+
+    fn error(&mut self, error: &ErrorData) {
+        self.send(Err(error.clone()));
+    }
+
+    fn set_position(&mut self, position: Position) {
+        self.position = Some(position);
+    }
+
+    fn should_stop(&self) -> bool {
+        self.hung_up
+    }
+}
@@ -0,0 +1,199 @@
+// Copyright 2013 The SAX-RS Developers. For a full listing of the authors,
+// refer to the AUTHORS file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Parsing of real-world HTML, using libxml2's HTML parser
+//!
+//! The results are the same `ParseEvent`s that are produced for XML
+//! documents. The HTML parser is tolerant of broken markup: missing end tags
+//! are implied, the `html` and `body` elements are added when they are left
+//! out, and entities such as `&nbsp;` are expanded into `Characters`. Elements
+//! are always reported as `StartElement` and `EndElement` events, and the
+//! contents of `script` and `style` elements as `CdataBlock` events.
+//!
+//! # Example
+//!
+//! ~~~rust
+//! let parser = sax::html::parse_str("<p>Hello<p>World");
+//! for result in parser.iter() {
+//!     println!("{}", result);
+//! }
+//! ~~~
+
+use libc::{c_char, c_int};
+use std::mem;
+use std::ptr::null;
+
+use super::{ffi, extfn, init_parser, events_channel};
+use super::{Events, ParserConfig, ParseOutcome, SaxHandler};
+use super::error::ErrorReport;
+
+/// Parses an HTML document held in a string.
+pub fn parse_str(src: &str) -> Events {
+    parse_bytes(src.as_bytes())
+}
+
+/// Parses an HTML document held in memory. The encoding is taken from the
+/// byte order mark or a `<meta>` tag, unless the default configuration is
+/// overridden with `parse_bytes_with_config`.
+pub fn parse_bytes(src: &[u8]) -> Events {
+    parse_bytes_with_config(&ParserConfig::new(), src)
+}
+
+/// Parses an HTML document held in memory, using the settings of `config`
+/// that apply to HTML. Namespaces, entity handling and the options that
+/// concern the DTD are ignored.
+///
+/// The parse runs on a separate task, just like `ParserConfig::parse_bytes`.
+pub fn parse_bytes_with_config(config: &ParserConfig, src: &[u8]) -> Events {
+    init_parser();
+
+    let config = html_config(config);
+    let src = Vec::from_slice(src);
+    let (handler, events) = events_channel(&config);
+    spawn(proc() {
+        let mut handler = handler;
+        let (outcome, report) = parse_memory(&config, src.as_slice(), &mut handler);
+        handler.finish(outcome, report);
+    });
+    events
+}
+
+/// Parses an HTML document on the current task, passing each event directly
+/// to `handler`. Returns how the parse ended, and a report of the errors that
+/// were seen.
+pub fn parse_with_handler<H: SaxHandler>(src: &str,
+                                         handler: &mut H) -> (ParseOutcome, ErrorReport) {
+    init_parser();
+    parse_memory(&html_config(&ParserConfig::new()), src.as_bytes(), handler)
+}
+
+/// Strips the settings that the HTML parser has no use for. It only ever
+/// calls the SAX1 element callbacks, and never resolves entities through the
+/// handler.
+fn html_config(config: &ParserConfig) -> ParserConfig {
+    let mut config = config.clone()
+        .namespaces(false)
+        .expand_entities(true)
+        .external_entities(false);
+    config.options &= ffi::XML_PARSE_NOBLANKS | ffi::XML_PARSE_NONET | ffi::XML_PARSE_HUGE;
+    config
+}
+
+/// The `htmlParserOption` flags for a configuration. The flags that the HTML
+/// parser shares with the XML parser have the same values.
+fn html_options(config: &ParserConfig) -> c_int {
+    let mut options = ffi::HTML_PARSE_RECOVER | config.options;
+    if config.encoding.is_some() { options |= ffi::XML_PARSE_IGNORE_ENC; }
+    options
+}
+
+/// Parses `src` in one go, dispatching the events to `handler`.
+fn parse_memory(config: &ParserConfig, src: &[u8],
+                handler: &mut SaxHandler) -> (ParseOutcome, ErrorReport) {
+    let mut context = extfn::Context::new(config, handler);
+    unsafe {
+        let ctxt = ffi::htmlCreatePushParserCtxt(&extfn::new_handler(config),
+                                                 mem::transmute(&mut context),
+                                                 null(), 0, null(),
+                                                 config.char_encoding());
+        if !ctxt.is_null() {
+            context.attach(ctxt);
+            ffi::htmlCtxtUseOptions(ctxt, html_options(config));
+            ffi::htmlParseChunk(ctxt, src.as_ptr() as *const c_char, src.len() as c_int, 1);
+            // this returns the last error even if the parser recovered from
+            // it, and the errors that it cannot recover from are fatal, which
+            // the context has already seen
+            context.parsed(0);
+        }
+        context.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_str, parse_with_handler};
+    use super::super::{StartElement, EndElement, Characters, Dtd};
+    use super::super::{SaxHandler, Complete};
+
+    #[test]
+    fn test_implied_tags() {
+        let sax = parse_str("<p class=\"first\">one &amp; only<p>two");
+        let mut tags = Vec::new();
+        let mut text = String::new();
+        for result in sax.iter() {
+            match result {
+                Ok(StartElement(name, atts)) => { tags.push(format!("{}{}", name, atts)); }
+                Ok(EndElement(name)) => { tags.push(format!("/{:s}", name)); }
+                Ok(Characters(chars)) => { text.push_str(chars.as_slice()); }
+                _ => { }
+            }
+        }
+        let t: Vec<&str> = tags.iter().map(|t| t.as_slice()).collect();
+        assert_eq!(t.as_slice(),
+                   &["html", "body", "p class=\"first\"", "/p", "p", "/p", "/body", "/html"]);
+        assert_eq!(text.as_slice(), "one & onlytwo");
+    }
+
+    #[test]
+    fn test_boolean_attribute() {
+        let mut found = false;
+        for result in parse_str("<input disabled>").iter() {
+            match result {
+                Ok(StartElement(ref name, ref atts)) if name.as_slice() == "input" => {
+                    assert_eq!(atts.find("disabled"), Some(""));
+                    found = true;
+                }
+                _ => { }
+            }
+        }
+        assert!(found);
+    }
+
+    #[test]
+    fn test_unnamed_doctype() {
+        let dtd: Vec<String> = parse_str("<!DOCTYPE><p>x").iter()
+            .filter_map(|result| match result {
+                Ok(Dtd(event)) => Some(event.to_string()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(dtd, vec!["<!DOCTYPE >".to_string()]);
+    }
+
+    #[test]
+    fn test_recovered_errors_complete() {
+        struct Ignore;
+        impl SaxHandler for Ignore {}
+
+        let (outcome, report) = parse_with_handler("<p>one</b><p>two", &mut Ignore);
+        assert_eq!(outcome, Complete);
+        assert!(!report.is_empty());
+    }
+
+    #[test]
+    fn test_script_with_handler() {
+        struct Script { code: String }
+
+        impl SaxHandler for Script {
+            fn cdata_block(&mut self, value: &str) {
+                self.code.push_str(value);
+            }
+        }
+
+        let mut handler = Script { code: String::new() };
+        parse_with_handler("<head><script>if (a < b) {}</script></head>", &mut handler);
+        assert_eq!(handler.code.as_slice(), "if (a < b) {}");
+    }
+}
@@ -0,0 +1,185 @@
+// Copyright 2014 The SAX-RS Developers. For a full listing of the authors,
+// refer to the AUTHORS file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Incremental parsing of documents that arrive in pieces
+
+use libc::{c_char, c_int};
+use std::comm::{Receiver, SyncSender, sync_channel};
+use std::mem;
+use std::ptr::null;
+
+use super::{ffi, extfn, init_parser, events_channel};
+use super::{Events, ParserConfig, ParseOutcome, SaxHandler};
+use super::error::ErrorReport;
+
+enum Input {
+    Chunk(Vec<u8>),
+    Terminate,
+}
+
+/// A parser that is fed the document a chunk at a time, wrapping libxml2's
+/// push parser.
+///
+/// The chunks are parsed on a separate task, and the results are sent to the
+/// `Events` that were returned along with the parser, just like `parse_str`.
+///
+/// # Example
+///
+/// ~~~rust
+/// let (mut parser, events) = sax::PushParser::new();
+/// parser.feed(b"<hello><a foo=\"ba");
+/// parser.feed(b"r\">test</a></hello>");
+/// parser.finish();
+/// for result in events.iter() {
+///     println!("{}", result);
+/// }
+/// ~~~
+pub struct PushParser {
+    input: SyncSender<Input>,
+}
+
+impl PushParser {
+    /// Creates a push parser with the default configuration.
+    pub fn new() -> (PushParser, Events) {
+        PushParser::with_config(&ParserConfig::new())
+    }
+
+    /// Creates a push parser that uses the supplied configuration.
+    pub fn with_config(config: &ParserConfig) -> (PushParser, Events) {
+        init_parser();
+
+        let config = config.clone();
+        let (input, chunks) = sync_channel(config.buffer_size);
+        let (handler, events) = events_channel(&config);
+        spawn(proc() {
+            let mut handler = handler;
+            let (outcome, report) = parse_chunks(&config, chunks, &mut handler);
+            handler.finish(outcome, report);
+        });
+        (PushParser { input: input }, events)
+    }
+
+    /// Queues the next chunk of the document for parsing. Chunks can be split
+    /// at any byte, including in the middle of a tag or a multi-byte
+    /// character.
+    ///
+    /// This blocks while `buffer_size` chunks are already waiting to be
+    /// parsed. Returns `false` if the parse has stopped, such as after a
+    /// fatal error with `FailFast` or once the `Events` have been dropped,
+    /// in which case the chunk is thrown away.
+    pub fn feed(&mut self, chunk: &[u8]) -> bool {
+        self.input.send_opt(Chunk(Vec::from_slice(chunk))).is_ok()
+    }
+
+    /// Signals the end of the document. The events are closed once the
+    /// remaining input has been parsed. Returns `false` if the parse had
+    /// already stopped.
+    ///
+    /// If the parser is dropped without being finished, any input that is
+    /// still pending will be parsed but the document is never terminated,
+    /// and the outcome of the parse is `Cancelled`.
+    pub fn finish(self) -> bool {
+        self.input.send_opt(Terminate).is_ok()
+    }
+}
+
+/// Parses the chunks of a document as they arrive, dispatching the events to
+/// `handler`.
+fn parse_chunks(config: &ParserConfig, chunks: Receiver<Input>,
+                handler: &mut SaxHandler) -> (ParseOutcome, ErrorReport) {
+    let mut context = extfn::Context::new(config, handler);
+    unsafe {
+        let ctxt = ffi::xmlCreatePushParserCtxt(&extfn::new_handler(config),
+                                                mem::transmute(&mut context),
+                                                null(), 0, null());
+        if !ctxt.is_null() {
+            context.attach(ctxt);
+            ffi::xmlCtxtUseOptions(ctxt, config.options());
+            if config.char_encoding() != ffi::XML_CHAR_ENCODING_NONE {
+                ffi::xmlSwitchEncoding(ctxt, config.char_encoding());
+            }
+            let mut terminated = false;
+            for chunk in chunks.iter() {
+                match chunk {
+                    Chunk(data) => {
+                        ffi::xmlParseChunk(ctxt, data.as_ptr() as *const c_char,
+                                           data.len() as c_int, 0);
+                    }
+                    Terminate => {
+                        context.parsed(ffi::xmlParseChunk(ctxt, null(), 0, 1));
+                        terminated = true;
+                        break;
+                    }
+                }
+                // hang up, so that the next chunk fed in is refused
+                if context.is_stopped() {
+                    break;
+                }
+            }
+            if !terminated && !context.is_stopped() {
+                context.cancel();
+            }
+        }
+        context.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PushParser;
+    use super::super::{EndDocument, StartElement, EndElement, Characters};
+    use super::super::{ParserConfig, FailFast, Failed};
+
+    #[test]
+    fn test_feed_chunks() {
+        let (mut parser, events) = PushParser::new();
+        let src = b"<hello><this /><a foo=\"bar\">test</a></hello>";
+        for chunk in src.chunks(5) {
+            parser.feed(chunk);
+        }
+        parser.finish();
+
+        let mut tags = Vec::new();
+        let mut text = String::new();
+        for result in events.iter() {
+            match result {
+                Ok(StartElement(name, _)) => { tags.push(name); }
+                Ok(EndElement(name)) => { tags.push(format!("/{:s}", name)); }
+                Ok(Characters(chars)) => { text.push_str(chars.as_slice()); }
+                Ok(EndDocument) => { break }
+                _ => { }
+            }
+        }
+        let t: Vec<&str> = tags.iter().map(|t| t.as_slice()).collect();
+        assert_eq!(t.as_slice(),
+                   &["hello", "this", "/this", "a", "/a", "/hello"]);
+        assert_eq!(text.as_slice(), "test");
+    }
+
+    #[test]
+    fn test_feed_after_stop() {
+        let config = ParserConfig::new().error_policy(FailFast);
+        let (mut parser, events) = PushParser::with_config(&config);
+        assert!(parser.feed(b"<a></b>"));
+        for _ in events.iter() {}
+        match events.outcome() {
+            Some(Failed(_)) => {}
+            outcome => fail!("unexpected outcome {}", outcome),
+        }
+        // the parse stopped at the mismatched tag, so nothing more is taken
+        assert!(!parser.feed(b"<c/>"));
+        assert!(!parser.finish());
+    }
+}
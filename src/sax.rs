@@ -18,22 +18,41 @@
 
 #![crate_type = "lib"]
 
-#![feature(globs)]
+#![feature(globs, unsafe_destructor)]
 
 extern crate libc;
 extern crate sync;
 
 use libc::{c_char, c_int};
+use std::cell::{Cell, RefCell};
 use std::mem;
-use std::comm::{Receiver, channel};
+use std::raw;
+use std::str;
+use std::comm::{Receiver, TryRecvError, Disconnected, sync_channel};
+use std::slice;
 use std::string;
-use std::io::{File, IoResult};
+use std::vec;
+use std::io::{BufReader, File, IoResult, Reader};
 use std::fmt;
+use std::from_str::{FromStr, from_str};
 
-use error::ErrorData;
+use dtd::DtdEvent;
+use error::{ErrorData, ErrorReport};
+use handler::{ChannelHandler, Message, EventMessage, EndMessage};
 
+pub use atom::{Atom, AtomQName, Names};
+pub use borrowed::ParseEventRef;
+pub use handler::SaxHandler;
+pub use push::PushParser;
+
+pub mod atom;
+pub mod borrowed;
+pub mod dtd;
 pub mod error;
 pub mod ffi;
+pub mod handler;
+pub mod html;
+pub mod push;
 mod extfn;
 
 /// Events to be sent by the parser.
@@ -47,12 +66,34 @@ pub enum ParseEvent {
     StartElement(String, Attributes),
     /// A closing tag was parsed.
     EndElement(String),
+    /// An opening tag was parsed with namespace processing enabled. This
+    /// carries the resolved name of the element, its attributes, and the
+    /// namespaces declared on the element itself.
+    StartElementNs(QName, Attributes, Vec<Namespace>),
+    /// A closing tag was parsed with namespace processing enabled.
+    EndElementNs(QName),
+    /// An opening tag was parsed with `intern_names` enabled. This carries
+    /// the same data as `StartElementNs`, but with the names interned as
+    /// atoms. Without namespace processing no namespaces are declared.
+    StartElementAtom(AtomQName, Attributes, Vec<Namespace>),
+    /// A closing tag was parsed with `intern_names` enabled.
+    EndElementAtom(AtomQName),
     /// Some characters between tags have been recived.
     Characters(String),
+    /// A reference to a general entity that was left unexpanded. This is
+    /// only sent when `expand_entities` is disabled.
+    EntityReference(String),
+    /// Whitespace that is not significant to the document. This is only sent
+    /// when the parser is configured with `ReportWhitespace`.
+    IgnorableWhitespace(String),
     /// A comment tag was parsed.
     Comment(String),
     /// A `CDATA` block was parsed.
     CdataBlock(String),
+    /// A processing instruction was parsed, with its target and data.
+    ProcessingInstruction(String, Option<String>),
+    /// Part of the document type declaration was parsed.
+    Dtd(DtdEvent),
 }
 
 impl fmt::Show for ParseEvent {
@@ -62,17 +103,227 @@ impl fmt::Show for ParseEvent {
             EndDocument => write!(fmt, "END DOCUMENT"),
             StartElement(ref name, ref atts) => write!(fmt, "<{}{}>", *name, atts),
             EndElement(ref name) => write!(fmt, "</{}>", *name),
+            StartElementNs(ref name, ref atts, ref namespaces) => {
+                try!(write!(fmt, "<{}", *name));
+                for ns in namespaces.iter() {
+                    try!(write!(fmt, "{}", *ns));
+                }
+                write!(fmt, "{}>", atts)
+            }
+            EndElementNs(ref name) => write!(fmt, "</{}>", *name),
+            StartElementAtom(ref name, ref atts, ref namespaces) => {
+                try!(write!(fmt, "<{}", *name));
+                for ns in namespaces.iter() {
+                    try!(write!(fmt, "{}", *ns));
+                }
+                write!(fmt, "{}>", atts)
+            }
+            EndElementAtom(ref name) => write!(fmt, "</{}>", *name),
             Characters(ref ch) => write!(fmt, "{}", ch.clone()),
+            IgnorableWhitespace(ref ch) => write!(fmt, "{}", ch.clone()),
+            EntityReference(ref name) => write!(fmt, "&{};", *name),
             Comment(ref value) => write!(fmt, "<!--{}-->", *value),
             CdataBlock(ref value) => write!(fmt, "<![CDATA[{}]]>", *value),
+            ProcessingInstruction(ref target, Some(ref data)) => write!(fmt, "<?{} {}?>", *target, *data),
+            ProcessingInstruction(ref target, None) => write!(fmt, "<?{}?>", *target),
+            Dtd(ref event) => write!(fmt, "{}", *event),
         }
     }
 }
 
+/// A namespace-qualified name.
+#[deriving(PartialEq, Eq, Clone)]
+pub struct QName {
+    /// The name without its prefix, eg. `a` in `test:a`.
+    pub local_name: String,
+    /// The prefix used in the document, if any.
+    pub prefix: Option<String>,
+    /// The URI of the namespace that the prefix resolved to, if any.
+    pub namespace_uri: Option<String>,
+}
+
+impl fmt::Show for QName {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self.prefix {
+            Some(ref prefix) => write!(fmt, "{}:{}", *prefix, self.local_name),
+            None => write!(fmt, "{}", self.local_name),
+        }
+    }
+}
+
+/// A namespace declaration made on an element.
+#[deriving(PartialEq, Eq, Clone)]
+pub struct Namespace {
+    /// The declared prefix, or `None` for a default namespace declaration.
+    pub prefix: Option<String>,
+    /// The namespace URI.
+    pub uri: String,
+}
+
+impl fmt::Show for Namespace {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self.prefix {
+            Some(ref prefix) => write!(fmt, " xmlns:{}=\"{}\"", *prefix, self.uri),
+            None => write!(fmt, " xmlns=\"{}\"", self.uri),
+        }
+    }
+}
+
+/// The namespace URI that the `xml` prefix is always bound to.
+pub static XML_NAMESPACE: &'static str = "http://www.w3.org/XML/1998/namespace";
+
+/// Tracks the namespace declarations that are in scope while handling
+/// `StartElementNs` and `EndElementNs` events. This is needed to resolve
+/// QNames that appear in attribute values.
+///
+/// # Example
+///
+/// ~~~rust
+/// match event {
+///     sax::StartElementNs(name, atts, namespaces) => {
+///         scope.push(namespaces.as_slice());
+///         let ty = atts.parse_qname("xsi:type", &scope);
+///     }
+///     sax::EndElementNs(_) => scope.pop(),
+///     _ => (),
+/// }
+/// ~~~
+#[deriving(Clone)]
+pub struct NamespaceScope {
+    frames: Vec<Vec<Namespace>>,
+}
+
+impl NamespaceScope {
+    pub fn new() -> NamespaceScope {
+        NamespaceScope { frames: Vec::new() }
+    }
+
+    /// Enters an element, bringing the namespaces it declares into scope.
+    pub fn push(&mut self, namespaces: &[Namespace]) {
+        self.frames.push(Vec::from_slice(namespaces));
+    }
+
+    /// Leaves the current element.
+    pub fn pop(&mut self) {
+        self.frames.pop();
+    }
+
+    /// Finds the URI bound to a prefix, or to the default namespace if the
+    /// prefix is `None`.
+    pub fn resolve<'a>(&'a self, prefix: Option<&str>) -> Option<&'a str> {
+        if prefix == Some("xml") { return Some(XML_NAMESPACE) }
+        for frame in self.frames.iter().rev() {
+            for ns in frame.iter().rev() {
+                if ns.prefix.as_ref().map(|p| p.as_slice()) == prefix {
+                    // an empty URI undeclares the default namespace
+                    return if ns.uri.is_empty() { None } else { Some(ns.uri.as_slice()) };
+                }
+            }
+        }
+        None
+    }
+}
+
+/// An attribute of an element.
 #[deriving(PartialEq, Eq, Clone)]
 pub struct Attribute {
-    name: String,
+    /// The qualified name of the attribute, as it appears in the document.
+    name: NameBuf,
     value: String,
+    /// Only available when namespace processing is enabled.
+    namespace_uri: Option<String>,
+}
+
+impl Attribute {
+    /// The qualified name of the attribute, as it appears in the document.
+    pub fn name<'a>(&'a self) -> &'a str {
+        self.name.as_slice()
+    }
+
+    /// The qualified name of the attribute as an atom. This is only
+    /// available when `intern_names` is enabled.
+    pub fn atom<'a>(&'a self) -> Option<&'a Atom> {
+        match self.name {
+            SharedName(ref atom) => Some(atom),
+            OwnedName(_) => None,
+        }
+    }
+
+    /// The name without its prefix, eg. `foo` in `test:foo`.
+    pub fn local_name<'a>(&'a self) -> &'a str {
+        self.name.as_slice().split(':').last().unwrap()
+    }
+
+    pub fn value<'a>(&'a self) -> &'a str {
+        self.value.as_slice()
+    }
+
+    /// The URI of the namespace of the attribute. This is only available
+    /// when namespace processing is enabled.
+    pub fn namespace_uri<'a>(&'a self) -> Option<&'a str> {
+        self.namespace_uri.as_ref().map(|uri| uri.as_slice())
+    }
+}
+
+impl fmt::Show for Attribute {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "{}=\"{}\"", self.name(), self.value)
+    }
+}
+
+/// The name of an attribute, which is interned when `intern_names` is
+/// enabled.
+#[deriving(Clone)]
+enum NameBuf {
+    OwnedName(String),
+    SharedName(Atom),
+}
+
+impl Str for NameBuf {
+    fn as_slice<'a>(&'a self) -> &'a str {
+        match *self {
+            OwnedName(ref name) => name.as_slice(),
+            SharedName(ref atom) => atom.as_slice(),
+        }
+    }
+}
+
+impl PartialEq for NameBuf {
+    /// Names are equal by content, however they are stored.
+    fn eq(&self, other: &NameBuf) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl Eq for NameBuf {}
+
+/// An attribute value that could not be looked up or converted.
+#[deriving(PartialEq, Eq, Clone)]
+pub enum AttributeError {
+    /// There is no attribute with this name.
+    MissingAttribute(String),
+    /// The named attribute has a value that could not be converted.
+    InvalidValue(String, String),
+    /// The named attribute has a QName value with a prefix that is not
+    /// bound to a namespace.
+    UnboundPrefix(String, String),
+}
+
+impl fmt::Show for AttributeError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            MissingAttribute(ref name) => {
+                write!(fmt, "Could not find an attribute with the name \"{}\"", *name)
+            }
+            InvalidValue(ref name, ref value) => {
+                write!(fmt, "The attribute \"{}\" has an invalid value \"{}\"", *name, *value)
+            }
+            UnboundPrefix(ref name, ref value) => {
+                write!(fmt, "The attribute \"{}\" has a QName value \"{}\" with an unbound prefix",
+                       *name, *value)
+            }
+        }
+    }
 }
 
 /// A list of attributes
@@ -80,29 +331,65 @@ pub struct Attribute {
 pub struct Attributes(Vec<Attribute>);
 
 impl Attributes {
-    unsafe fn from_buf(atts: *const *const ffi::xmlChar) -> Attributes {
-        let mut ret = Vec::new();
-        let mut ptr = atts as *const *const c_char;
-        while !ptr.is_null() && !(*ptr).is_null() {
-            ret.push(
-                Attribute {
-                    name: string::raw::from_buf(*ptr as *const u8),
-                    value: string::raw::from_buf(*ptr.offset(1) as *const u8),
-                }
-            );
-            ptr = ptr.offset(2);
-        }
-        Attributes(ret)
+    /// The number of attributes.
+    pub fn len(&self) -> uint {
+        let Attributes(ref s) = *self;
+        s.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Iterates over the attributes in the order they appear in the
+    /// document.
+    pub fn iter<'a>(&'a self) -> slice::Items<'a, Attribute> {
+        let Attributes(ref s) = *self;
+        s.iter()
+    }
+
+    /// Consumes the attributes, iterating over them in the order they appear
+    /// in the document.
+    pub fn move_iter(self) -> vec::MoveItems<Attribute> {
+        let Attributes(s) = self;
+        s.move_iter()
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.find(name).is_some()
     }
 
     pub fn find<'a>(&'a self, name: &str) -> Option<&'a str> {
         let Attributes(ref s) = *self;
-        s.iter().find(|att| name == att.name.as_slice())
+        s.iter().find(|att| name == att.name())
                 .map(|att| att.value.as_slice())
     }
 
+    /// Like `find`, but returns a `MissingAttribute` error if there is no
+    /// attribute with this name.
+    pub fn require<'a>(&'a self, name: &str) -> Result<&'a str, AttributeError> {
+        self.find(name).ok_or(MissingAttribute(name.to_string()))
+    }
+
+    /// Finds an attribute by its local name and namespace URI. This only
+    /// matches attributes parsed with namespace processing enabled.
+    pub fn find_ns<'a>(&'a self, namespace_uri: &str, local_name: &str) -> Option<&'a str> {
+        let Attributes(ref s) = *self;
+        s.iter().find(|att| {
+            att.namespace_uri.as_ref().map_or(false, |uri| namespace_uri == uri.as_slice())
+                && local_name == att.local_name()
+        }).map(|att| att.value.as_slice())
+    }
+
+    /// # Failure
+    ///
+    /// Fails if there is no attribute with this name. Use `require` to
+    /// handle this as an error instead.
     pub fn get<'a>(&'a self, name: &str) -> &'a str {
-        self.find(name).expect(format!("Could not find an attribute with the name \"{}\"", name).as_slice())
+        match self.require(name) {
+            Ok(value) => value,
+            Err(err) => fail!("{}", err),
+        }
     }
 
     pub fn find_clone(&self, name: &str) -> Option<String> {
@@ -112,26 +399,728 @@ impl Attributes {
     pub fn get_clone(&self, name: &str) -> String {
         String::from_str(self.get(name))
     }
+
+    /// Converts the value of an attribute with `FromStr`.
+    ///
+    /// # Example
+    ///
+    /// ~~~rust
+    /// let width: uint = try!(atts.parse("width"));
+    /// ~~~
+    pub fn parse<T: FromStr>(&self, name: &str) -> Result<T, AttributeError> {
+        self.require(name).and_then(|value| convert(name, value, |v| from_str(v)))
+    }
+
+    /// Like `parse`, but returns `default` if there is no attribute with
+    /// this name. A value that cannot be converted is still an error.
+    pub fn parse_or<T: FromStr>(&self, name: &str, default: T) -> Result<T, AttributeError> {
+        match self.find(name) {
+            Some(value) => convert(name, value, |v| from_str(v)),
+            None => Ok(default),
+        }
+    }
+
+    /// Converts the value of an attribute as an XML Schema `boolean`, which
+    /// is one of `true`, `false`, `1` or `0`.
+    pub fn parse_bool(&self, name: &str) -> Result<bool, AttributeError> {
+        self.require(name).and_then(|value| {
+            convert(name, value, |v| match v.trim_chars(XML_WHITESPACE) {
+                "true" | "1" => Some(true),
+                "false" | "0" => Some(false),
+                _ => None,
+            })
+        })
+    }
+
+    /// Converts the value of an attribute as an XML Schema list, splitting
+    /// it on whitespace and converting each item with `FromStr`.
+    pub fn parse_list<T: FromStr>(&self, name: &str) -> Result<Vec<T>, AttributeError> {
+        let value = try!(self.require(name));
+        let mut items = Vec::new();
+        for item in value.split(XML_WHITESPACE).filter(|item| !item.is_empty()) {
+            items.push(try!(convert(name, item, |v| from_str(v))));
+        }
+        Ok(items)
+    }
+
+    /// Converts the value of an attribute to a QName, resolving its prefix
+    /// against the namespaces that are in scope. An unprefixed name is in
+    /// the default namespace, if there is one.
+    pub fn parse_qname(&self, name: &str,
+                       scope: &NamespaceScope) -> Result<QName, AttributeError> {
+        self.require(name).and_then(|value| {
+            let qname = value.trim_chars(XML_WHITESPACE);
+            let (prefix, local_name) = match qname.find(':') {
+                Some(i) => (Some(qname.slice_to(i)), qname.slice_from(i + 1)),
+                None => (None, qname),
+            };
+            if local_name.is_empty() || local_name.contains_char(':') || prefix == Some("") {
+                return Err(InvalidValue(name.to_string(), value.to_string()));
+            }
+            let namespace_uri = scope.resolve(prefix);
+            if prefix.is_some() && namespace_uri.is_none() {
+                return Err(UnboundPrefix(name.to_string(), value.to_string()));
+            }
+            Ok(QName {
+                local_name:     local_name.to_string(),
+                prefix:         prefix.map(|p| p.to_string()),
+                namespace_uri:  namespace_uri.map(|uri| uri.to_string()),
+            })
+        })
+    }
+}
+
+/// The characters matched by the `S` production of the XML spec.
+static XML_WHITESPACE: &'static [char] = &[' ', '\t', '\r', '\n'];
+
+/// Converts an attribute value, turning a failure into an `InvalidValue`
+/// error.
+fn convert<T>(name: &str, value: &str, f: |&str| -> Option<T>) -> Result<T, AttributeError> {
+    f(value).ok_or_else(|| InvalidValue(name.to_string(), value.to_string()))
+}
+
+impl Index<uint, Attribute> for Attributes {
+    /// Gets an attribute by its position in the document.
+    ///
+    /// # Failure
+    ///
+    /// Fails if the index is out of bounds.
+    fn index<'a>(&'a self, index: &uint) -> &'a Attribute {
+        let Attributes(ref s) = *self;
+        &s[*index]
+    }
 }
 
 impl fmt::Show for Attributes {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        let Attributes(ref s) = *self;
-        for att in s.iter() {
-            try!(write!(fmt, " {}=\"{}\"", att.name, att.value));
+        for att in self.iter() {
+            try!(write!(fmt, " {}", att));
         }
         Ok(())
     }
 }
 
+unsafe fn from_buf_opt(ptr: *const ffi::xmlChar) -> Option<String> {
+    if ptr.is_null() { None } else { Some(string::raw::from_buf(ptr as *const u8)) }
+}
+
+/// Borrows a null-terminated string from libxml2, which holds all of its
+/// text as UTF-8.
+unsafe fn str_from_buf<'a>(ptr: *const ffi::xmlChar) -> &'a str {
+    str_from_buf_len(ptr, libc::strlen(ptr as *const c_char) as uint)
+}
+
+unsafe fn str_from_buf_len<'a>(ptr: *const ffi::xmlChar, len: uint) -> &'a str {
+    str::raw::from_utf8(slice_from_buf(ptr, len))
+}
+
+unsafe fn str_from_buf_opt<'a>(ptr: *const ffi::xmlChar) -> Option<&'a str> {
+    if ptr.is_null() { None } else { Some(str_from_buf(ptr)) }
+}
+
+/// Borrows an array of `len` elements from libxml2.
+unsafe fn slice_from_buf<'a, T>(ptr: *const T, len: uint) -> &'a [T] {
+    if len == 0 { return &[] }
+    mem::transmute(raw::Slice { data: ptr, len: len })
+}
+
 /// Either a parse event wrapped in `Ok` or some Error data wrapped in `Err`.
 pub type ParseResult = Result<ParseEvent, ErrorData>;
 
+/// How a parse ended.
+#[deriving(PartialEq, Eq, Clone, Show)]
+pub enum ParseOutcome {
+    /// The whole document was parsed, and it is well-formed.
+    Complete,
+    /// The consumer stopped the parse before the end of the document.
+    Cancelled,
+    /// The document is not well-formed, or broke the security limits of the
+    /// parser. This holds the first fatal error, which is usually what
+    /// stopped the parse.
+    Failed(ErrorData),
+}
+
+impl ParseOutcome {
+    /// Whether the whole document was parsed successfully.
+    pub fn is_complete(&self) -> bool {
+        *self == Complete
+    }
+}
+
+/// A location in the source document.
+#[deriving(PartialEq, Eq, Clone, Show)]
+pub struct Position {
+    /// The line number, starting at `1`.
+    pub line: uint,
+    /// The column number, starting at `1`.
+    pub column: uint,
+    /// The number of bytes of input consumed so far.
+    pub byte_offset: uint,
+}
+
+/// Receives the results of a parse that is running on another task.
+///
+/// Dropping the `Events` stops the parse, so there is no need to read a
+/// large document to the end when only the start of it is of interest.
+pub struct Events {
+    receiver: Receiver<Message>,
+    position: Cell<Option<Position>>,
+    report: RefCell<Option<ErrorReport>>,
+    outcome: RefCell<Option<ParseOutcome>>,
+}
+
+impl Events {
+    fn new(receiver: Receiver<Message>) -> Events {
+        Events {
+            receiver: receiver,
+            position: Cell::new(None),
+            report: RefCell::new(None),
+            outcome: RefCell::new(None),
+        }
+    }
+
+    /// Unpacks a message from the parser, keeping the outcome and report that
+    /// are sent once the parse has finished.
+    fn receive(&self, message: Message) -> Option<ParseResult> {
+        match message {
+            EventMessage(result, position) => {
+                self.position.set(position);
+                Some(result)
+            }
+            EndMessage(outcome, report) => {
+                *self.outcome.borrow_mut() = Some(outcome);
+                *self.report.borrow_mut() = Some(report);
+                None
+            }
+        }
+    }
+
+    /// Blocks until the next result is available.
+    ///
+    /// # Failure
+    ///
+    /// Fails if the parse has finished and there are no results left.
+    pub fn recv(&self) -> ParseResult {
+        match self.recv_opt() {
+            Ok(result) => result,
+            Err(()) => fail!("no more results: the parse has finished"),
+        }
+    }
+
+    /// Blocks until the next result is available, returning `Err` once the
+    /// parse has finished and there are no results left.
+    pub fn recv_opt(&self) -> Result<ParseResult, ()> {
+        self.receiver.recv_opt().and_then(|message| self.receive(message).ok_or(()))
+    }
+
+    /// Returns the next result without blocking, if one is available.
+    pub fn try_recv(&self) -> Result<ParseResult, TryRecvError> {
+        self.receiver.try_recv().and_then(|message| self.receive(message).ok_or(Disconnected))
+    }
+
+    /// Returns an iterator that blocks waiting for results, ending once the
+    /// parse has finished.
+    pub fn iter<'a>(&'a self) -> EventsIter<'a> {
+        EventsIter { events: self }
+    }
+
+    /// The position of the most recently received result. This is always
+    /// `None` unless positions have been enabled in the `ParserConfig`.
+    pub fn position(&self) -> Option<Position> {
+        self.position.get()
+    }
+
+    /// The errors seen during the parse. This is only available once the
+    /// parse has finished and every result has been received.
+    pub fn report(&self) -> Option<ErrorReport> {
+        self.report.borrow().clone()
+    }
+
+    /// How the parse ended. Like `report`, this is only available once every
+    /// result has been received.
+    pub fn outcome(&self) -> Option<ParseOutcome> {
+        self.outcome.borrow().clone()
+    }
+}
+
+/// An iterator over the results received by `Events`.
+pub struct EventsIter<'a> {
+    events: &'a Events,
+}
+
+impl<'a> Iterator<ParseResult> for EventsIter<'a> {
+    fn next(&mut self) -> Option<ParseResult> {
+        self.events.recv_opt().ok()
+    }
+}
+
+/// How whitespace-only character data should be handled.
+#[deriving(PartialEq, Eq, Clone, Show)]
+pub enum WhitespacePolicy {
+    /// Send whitespace as `Characters` events, like any other text.
+    PreserveWhitespace,
+    /// Discard whitespace-only text entirely.
+    StripWhitespace,
+    /// Send whitespace-only text as `IgnorableWhitespace` events, so that it
+    /// can be told apart from significant text.
+    ReportWhitespace,
+}
+
+bitflags!(
+    #[doc = "The kinds of event that a parser reports to its handler. Events of"]
+    #[doc = "the kinds that are left out are dropped as early as possible:"]
+    #[doc = "where libxml2 allows it, the callback that would produce them is"]
+    #[doc = "never registered, and otherwise they are discarded before any"]
+    #[doc = "copy is made. Errors are always reported."]
+    #[doc = ""]
+    #[doc = "# Example"]
+    #[doc = ""]
+    #[doc = "~~~rust"]
+    #[doc = "let parser = sax::ParserConfig::new()"]
+    #[doc = "    .events(sax::StartElementEvents)"]
+    #[doc = "    .parse_str(XML_DATA);"]
+    #[doc = "~~~"]
+    flags EventMask: u32 {
+        #[doc = "`StartDocument` and `EndDocument`"]
+        static DocumentEvents               = 0x0001,
+        #[doc = "`StartElement`, `StartElementNs` and `StartElementAtom`"]
+        static StartElementEvents           = 0x0002,
+        #[doc = "`EndElement`, `EndElementNs` and `EndElementAtom`"]
+        static EndElementEvents             = 0x0004,
+        #[doc = "`Characters`"]
+        static CharacterEvents              = 0x0008,
+        #[doc = "`IgnorableWhitespace`"]
+        static WhitespaceEvents             = 0x0010,
+        #[doc = "`EntityReference`"]
+        static EntityReferenceEvents        = 0x0020,
+        #[doc = "`Comment`"]
+        static CommentEvents                = 0x0040,
+        #[doc = "`CdataBlock`"]
+        static CdataEvents                  = 0x0080,
+        #[doc = "`ProcessingInstruction`"]
+        static ProcessingInstructionEvents  = 0x0100,
+        #[doc = "`Dtd`"]
+        static DtdEvents                    = 0x0200,
+        #[doc = "Both the start and the end of elements"]
+        static ElementEvents                = 0x0006
+    }
+)
+
+/// Whether adjacent pieces of text are joined into a single event. libxml2
+/// splits text at the boundaries of its input buffer and around entity
+/// references, so a single run of text between two tags can arrive as
+/// several events.
+#[deriving(PartialEq, Eq, Clone, Show)]
+pub enum TextCoalescing {
+    /// Send text in the pieces that libxml2 reports it in. Unless whitespace
+    /// is preserved, each run of characters is still joined, so that the
+    /// whitespace policy sees all of it.
+    SplitText,
+    /// Join adjacent `Characters` into a single event, and adjacent
+    /// `CdataBlock`s likewise, but keep the two apart.
+    MergeText,
+    /// Join all adjacent text into a single `Characters` event, including
+    /// any `CDATA` sections within it.
+    MergeTextAndCdata,
+}
+
+/// The character encodings that libxml2 supports without iconv.
+#[deriving(PartialEq, Eq, Clone, Show)]
+pub enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    /// ISO-8859-1
+    Latin1,
+    Ascii,
+}
+
+impl Encoding {
+    fn to_constant(self) -> ffi::xmlCharEncoding {
+        match self {
+            Utf8    => ffi::XML_CHAR_ENCODING_UTF8,
+            Utf16Le => ffi::XML_CHAR_ENCODING_UTF16LE,
+            Utf16Be => ffi::XML_CHAR_ENCODING_UTF16BE,
+            Latin1  => ffi::XML_CHAR_ENCODING_8859_1,
+            Ascii   => ffi::XML_CHAR_ENCODING_ASCII,
+        }
+    }
+}
+
+/// What happens to the errors reported by the parser.
+#[deriving(PartialEq, Eq, Clone, Show)]
+pub enum ErrorPolicy {
+    /// Send each error to the handler as it is reported, and keep parsing
+    /// for as long as libxml2 is able to.
+    ReportErrors,
+    /// Send each error to the handler as it is reported, but stop the parse
+    /// at the first `Fatal` error.
+    FailFast,
+    /// Keep the errors out of the stream of events, and return them all in
+    /// the `ErrorReport` once the parse has finished.
+    CollectErrors,
+}
+
+/// Settings that control how a document is parsed.
+///
+/// # Security
+///
+/// The default configuration is safe to use with untrusted documents.
+/// Nothing is fetched over the network, external entities and the external
+/// subset are never loaded, and entity references may only nest
+/// `max_entity_depth` levels deep and expand to `max_entity_ratio` times
+/// the size of the input read so far. A document that breaks these limits
+/// stops the parse with an error whose kind is `SecurityViolation`. Each
+/// limit can only be relaxed by calling the method that controls it.
+///
+/// # Example
+///
+/// ~~~rust
+/// let parser = sax::ParserConfig::new()
+///     .namespaces(true)
+///     .parse_str(XML_DATA);
+/// ~~~
+#[deriving(Clone)]
+pub struct ParserConfig {
+    namespaces: bool,
+    buffer_size: uint,
+    whitespace: WhitespacePolicy,
+    coalesce: TextCoalescing,
+    expand_entities: bool,
+    external_entities: bool,
+    max_entity_depth: uint,
+    max_entity_ratio: uint,
+    encoding: Option<Encoding>,
+    error_policy: ErrorPolicy,
+    warnings_as_errors: bool,
+    positions: bool,
+    events: EventMask,
+    intern_names: bool,
+    /// The remaining `xmlParserOption` flags
+    options: c_int,
+}
+
+impl ParserConfig {
+    /// The default configuration, matching the behaviour of `parse_str`.
+    pub fn new() -> ParserConfig {
+        ParserConfig {
+            namespaces: false,
+            buffer_size: 1024,
+            whitespace: PreserveWhitespace,
+            coalesce: SplitText,
+            expand_entities: true,
+            external_entities: false,
+            max_entity_depth: 16,
+            max_entity_ratio: 10,
+            encoding: None,
+            error_policy: ReportErrors,
+            warnings_as_errors: false,
+            positions: false,
+            events: EventMask::all(),
+            intern_names: false,
+            options: ffi::XML_PARSE_NONET,
+        }
+    }
+
+    /// Report elements as `StartElementNs` and `EndElementNs` events instead
+    /// of `StartElement` and `EndElement`.
+    pub fn namespaces(mut self, enabled: bool) -> ParserConfig {
+        self.namespaces = enabled;
+        self
+    }
+
+    /// Sets how whitespace-only text between tags is handled. Defaults to
+    /// `PreserveWhitespace`.
+    pub fn whitespace(mut self, policy: WhitespacePolicy) -> ParserConfig {
+        self.whitespace = policy;
+        self
+    }
+
+    /// Sets whether adjacent pieces of text are joined before being sent.
+    /// Defaults to `SplitText`. The whitespace policy always applies to the
+    /// whole run of text rather than to each piece.
+    pub fn coalesce_text(mut self, mode: TextCoalescing) -> ParserConfig {
+        self.coalesce = mode;
+        self
+    }
+
+    /// Whether references to the general entities declared in the internal
+    /// subset are replaced by their content. When disabled, each reference is
+    /// reported as an `EntityReference` event instead, and references in
+    /// attribute values are left as they were written. Defaults to `true`.
+    pub fn expand_entities(mut self, enabled: bool) -> ParserConfig {
+        self.expand_entities = enabled;
+        self
+    }
+
+    /// Whether references to external parsed entities are resolved, loading
+    /// them from the system identifier given in their declaration. When
+    /// disabled, such a reference stops the parse with an `ExternalEntity`
+    /// violation. Defaults to `false`.
+    pub fn external_entities(mut self, enabled: bool) -> ParserConfig {
+        self.external_entities = enabled;
+        self
+    }
+
+    /// The deepest that entity references may nest inside the replacement
+    /// text of other entities. Defaults to `16`.
+    pub fn max_entity_depth(mut self, depth: uint) -> ParserConfig {
+        self.max_entity_depth = depth;
+        self
+    }
+
+    /// How many times larger than the input read so far the text produced
+    /// by expanding entities may grow, once it exceeds 64KiB. Defaults to
+    /// `10`.
+    pub fn max_entity_ratio(mut self, ratio: uint) -> ParserConfig {
+        self.max_entity_ratio = ratio;
+        self
+    }
+
+    /// Forces the encoding of the input, ignoring its byte order mark and
+    /// XML declaration. By default the encoding is detected from these, and
+    /// UTF-8 is assumed when neither says otherwise.
+    pub fn encoding(mut self, encoding: Encoding) -> ParserConfig {
+        self.encoding = Some(encoding);
+        self
+    }
+
+    /// Sets what happens to the errors reported by the parser. Whatever the
+    /// policy, every error is counted in the `ErrorReport` for the parse.
+    /// Defaults to `ReportErrors`.
+    pub fn error_policy(mut self, policy: ErrorPolicy) -> ParserConfig {
+        self.error_policy = policy;
+        self
+    }
+
+    /// Whether warnings are raised to the `Error` level. Defaults to
+    /// `false`.
+    pub fn warnings_as_errors(mut self, enabled: bool) -> ParserConfig {
+        self.warnings_as_errors = enabled;
+        self
+    }
+
+    /// Whether the position of each event is tracked. When enabled, it is
+    /// passed to `SaxHandler::set_position`, and is available from
+    /// `Events::position` as each result is received. Defaults to `false`.
+    pub fn positions(mut self, enabled: bool) -> ParserConfig {
+        self.positions = enabled;
+        self
+    }
+
+    /// Sets the kinds of event that are reported. Defaults to every kind.
+    pub fn events(mut self, mask: EventMask) -> ParserConfig {
+        self.events = mask;
+        self
+    }
+
+    /// Intern element and attribute names in the parser's own dictionary.
+    /// Elements are then reported as `StartElementAtom` and `EndElementAtom`
+    /// events, and attributes carry their names as atoms. The handler is
+    /// also given the dictionary as a `Names` table through
+    /// `SaxHandler::names`, which outlives the parse.
+    pub fn intern_names(mut self, enabled: bool) -> ParserConfig {
+        self.intern_names = enabled;
+        self
+    }
+
+    fn flag(mut self, flag: ffi::xmlParserOption, enabled: bool) -> ParserConfig {
+        if enabled { self.options |= flag; } else { self.options &= !flag; }
+        self
+    }
+
+    /// Keep parsing after recoverable errors (`XML_PARSE_RECOVER`).
+    pub fn recover(self, enabled: bool) -> ParserConfig {
+        self.flag(ffi::XML_PARSE_RECOVER, enabled)
+    }
+
+    /// Load the external subset named by the document type declaration
+    /// (`XML_PARSE_DTDLOAD`).
+    pub fn load_dtd(self, enabled: bool) -> ParserConfig {
+        self.flag(ffi::XML_PARSE_DTDLOAD, enabled)
+    }
+
+    /// Add the default values of attributes declared in the DTD to the
+    /// attributes of each element (`XML_PARSE_DTDATTR`). libxml2 only does
+    /// this when `namespaces` is enabled.
+    pub fn default_attributes(self, enabled: bool) -> ParserConfig {
+        self.flag(ffi::XML_PARSE_DTDATTR, enabled)
+    }
+
+    /// Validate against the DTD (`XML_PARSE_DTDVALID`). This implies
+    /// `load_dtd`. Note that libxml2 checks element content against the
+    /// tree it builds, so a streaming parse mostly reports problems found in
+    /// the DTD itself.
+    pub fn validate(self, enabled: bool) -> ParserConfig {
+        self.flag(ffi::XML_PARSE_DTDVALID, enabled)
+    }
+
+    /// Whether resources such as the external subset may be fetched over the
+    /// network. While disabled, `XML_PARSE_NONET` is set and any attempt is
+    /// reported as a `NetworkAccess` violation. Defaults to `false`.
+    pub fn network(self, enabled: bool) -> ParserConfig {
+        self.flag(ffi::XML_PARSE_NONET, !enabled)
+    }
+
+    /// Lift libxml2's hardcoded limits on the depth of the document and the
+    /// size of text nodes (`XML_PARSE_HUGE`).
+    pub fn huge(self, enabled: bool) -> ParserConfig {
+        self.flag(ffi::XML_PARSE_HUGE, enabled)
+    }
+
+    /// Report `CDATA` sections as `Characters` instead of `CdataBlock`
+    /// events (`XML_PARSE_NOCDATA`).
+    pub fn cdata_as_text(self, enabled: bool) -> ParserConfig {
+        self.flag(ffi::XML_PARSE_NOCDATA, enabled)
+    }
+
+    /// Drop blank text that libxml2 considers insignificant
+    /// (`XML_PARSE_NOBLANKS`). Unlike `StripWhitespace`, this uses libxml2's
+    /// own heuristics, and the blanks are never reported to the handler.
+    pub fn strip_blanks(self, enabled: bool) -> ParserConfig {
+        self.flag(ffi::XML_PARSE_NOBLANKS, enabled)
+    }
+
+    /// Remove redundant namespace declarations (`XML_PARSE_NSCLEAN`).
+    pub fn clean_namespaces(self, enabled: bool) -> ParserConfig {
+        self.flag(ffi::XML_PARSE_NSCLEAN, enabled)
+    }
+
+    /// The number of parse results that can be queued up before the parser
+    /// blocks, waiting for the receiver to catch up. This is also the number
+    /// of chunks that a `PushParser` queues up before `feed` blocks.
+    pub fn buffer_size(mut self, size: uint) -> ParserConfig {
+        self.buffer_size = size;
+        self
+    }
+
+    /// Parses the entire XML string using this configuration.
+    ///
+    /// The parse runs on a separate task, so results can be received while
+    /// the document is still being processed.
+    #[inline(never)]
+    pub fn parse_str(&self, src: &str) -> Events {
+        self.parse_bytes(src.as_bytes())
+    }
+
+    /// Parses a document held in memory using this configuration. Unlike
+    /// `parse_str`, the document can be in any encoding that libxml2
+    /// supports, and is decoded according to its byte order mark and XML
+    /// declaration unless an `encoding` is set.
+    pub fn parse_bytes(&self, src: &[u8]) -> Events {
+        init_parser();
+
+        let config = self.clone();
+        let src = Vec::from_slice(src);
+        let (handler, events) = events_channel(self);
+        spawn(proc() {
+            let mut handler = handler;
+            let (outcome, report) = parse_memory(&config, src.as_slice(), &mut handler);
+            handler.finish(outcome, report);
+        });
+        events
+    }
+
+    /// Parses the entire XML string on the current task, calling the methods
+    /// of `handler` as each event is produced. Returns how the parse ended,
+    /// and a report of the errors that were seen.
+    pub fn parse_with_handler<H: SaxHandler>(&self, src: &str,
+                                             handler: &mut H) -> (ParseOutcome, ErrorReport) {
+        init_parser();
+        parse_memory(self, src.as_bytes(), handler)
+    }
+
+    /// Parses a document from a reader using this configuration.
+    ///
+    /// The input is read on a separate task, a buffer at a time, so the whole
+    /// document never needs to be held in memory. The encoding is detected
+    /// by libxml2, so the input does not need to be UTF-8.
+    pub fn parse_reader<R: Reader + Send>(&self, reader: R) -> Events {
+        init_parser();
+
+        let config = self.clone();
+        let (handler, events) = events_channel(self);
+        spawn(proc() {
+            let mut handler = handler;
+            let mut reader = reader;
+            let (outcome, report) = parse_io(&config, &mut reader, &mut handler);
+            handler.finish(outcome, report);
+        });
+        events
+    }
+
+    /// Opens the file and streams it through `parse_reader`.
+    pub fn parse_file(&self, path: &Path) -> IoResult<Events> {
+        File::open(path).map(|file| self.parse_reader(file))
+    }
+
+    /// The `xmlParserOption` flags for this configuration.
+    fn options(&self) -> c_int {
+        let mut options = self.options;
+        if self.expand_entities { options |= ffi::XML_PARSE_NOENT; }
+        if self.encoding.is_some() { options |= ffi::XML_PARSE_IGNORE_ENC; }
+        options
+    }
+
+    /// The `xmlCharEncoding` to force on the input, if any.
+    fn char_encoding(&self) -> ffi::xmlCharEncoding {
+        self.encoding.map_or(ffi::XML_CHAR_ENCODING_NONE, |encoding| encoding.to_constant())
+    }
+}
+
+/// Creates a handler that sends its results to the returned `Events`.
+fn events_channel(config: &ParserConfig) -> (ChannelHandler, Events) {
+    let (sender, receiver) = sync_channel(config.buffer_size);
+    (ChannelHandler::new(sender), Events::new(receiver))
+}
+
+/// Parses `src` in one go, dispatching the events to `handler`.
+fn parse_memory(config: &ParserConfig, src: &[u8],
+                handler: &mut SaxHandler) -> (ParseOutcome, ErrorReport) {
+    parse_io(config, &mut BufReader::new(src), handler)
+}
+
+/// Parses the document read from `reader`, dispatching the events to
+/// `handler`.
+fn parse_io(config: &ParserConfig, reader: &mut Reader,
+            handler: &mut SaxHandler) -> (ParseOutcome, ErrorReport) {
+    let mut context = extfn::Context::new(config, handler);
+    let mut reader = reader;
+    unsafe {
+        let ctxt = ffi::xmlCreateIOParserCtxt(&extfn::new_handler(config),
+                                              mem::transmute(&mut context),
+                                              Some(extfn::read_reader), None,
+                                              mem::transmute(&mut reader),
+                                              config.char_encoding());
+        if !ctxt.is_null() {
+            context.attach(ctxt);
+            ffi::xmlCtxtUseOptions(ctxt, config.options());
+            context.parsed(ffi::xmlParseDocument(ctxt));
+        }
+        context.finish()
+    }
+}
+
+/// Ensures that the xml library is ready for use.
+fn init_parser() {
+    use sync::one::{Once, ONCE_INIT};
+    static mut INIT: Once = ONCE_INIT;
+    unsafe {
+        INIT.doit(|| {
+            ffi::xmlInitParser();
+            std::rt::at_exit(proc() ffi::xmlCleanupParser());
+        })
+    }
+}
+
 /// Parses the entire XML string.
 ///
 /// # Returns
 ///
-/// A receiver that recieves parse results.
+/// An `Events` receiver that recieves parse results as they are produced. The channel
+/// is bounded, so the parser will wait if the receiver falls behind. Once the
+/// document has been processed the channel is closed, and the outcome of the
+/// parse is available, even if the document was cut short.
 ///
 /// # Example
 ///
@@ -139,51 +1128,68 @@ pub type ParseResult = Result<ParseEvent, ErrorData>;
 /// let parser = sax::parse_str(XML_DATA);
 /// for result in parser.iter() {
 ///     match result {
-///         Ok(sax::StartDocument) => (),
-///         Ok(sax::EndDocument) => break,
 ///         Ok(event) => println!("{}", event),
 ///         Err(err) => println!("{}", err),
 ///     }
 /// }
+/// match parser.outcome() {
+///     Some(sax::Failed(err)) => println!("parse failed: {}", err),
+///     _ => (),
+/// }
 /// ~~~
-#[inline(never)]
-pub fn parse_str(src: &str) -> Receiver<ParseResult> {
-    // ensure that the xml library is ready for use
-    use sync::one::{Once, ONCE_INIT};
-    static mut INIT: Once = ONCE_INIT;
-    unsafe {
-        INIT.doit(|| {
-            ffi::xmlInitParser();
-            std::rt::at_exit(proc() ffi::xmlCleanupParser());
-        })
-    }
+pub fn parse_str(src: &str) -> Events {
+    ParserConfig::new().parse_str(src)
+}
 
-    let len = src.len() as c_int;
-    let (sender, receiver) = channel();
-    unsafe {
-        ffi::xmlSAXUserParseMemory(&extfn::new_handler(),
-                                   mem::transmute(&sender),
-                                   src.to_c_str().as_ptr(), len);
-    }
-    receiver
+/// Parses a document held in memory, detecting its encoding. This is the
+/// counterpart of `parse_str` for documents that are not UTF-8, such as
+/// UTF-16 or ISO-8859-1.
+pub fn parse_bytes(src: &[u8]) -> Events {
+    ParserConfig::new().parse_bytes(src)
+}
+
+/// Parses the entire XML string on the current task, passing each event
+/// directly to `handler` instead of sending it over a channel.
+pub fn parse_with_handler<H: SaxHandler>(src: &str,
+                                         handler: &mut H) -> (ParseOutcome, ErrorReport) {
+    ParserConfig::new().parse_with_handler(src, handler)
 }
 
-pub fn parse_file(path: &Path) -> IoResult<Receiver<ParseResult>> {
-    File::open(path).and_then(|mut file| file.read_to_string())
-                    .and_then(|contents| Ok(parse_str(contents.as_slice())))
+/// Parses a document from a reader, such as a socket or stdin, without
+/// reading it all into memory first.
+pub fn parse_reader<R: Reader + Send>(reader: R) -> Events {
+    ParserConfig::new().parse_reader(reader)
+}
+
+/// Parses the file at the given path, streaming it from disk.
+pub fn parse_file(path: &Path) -> IoResult<Events> {
+    ParserConfig::new().parse_file(path)
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{Attributes, Attribute};
+    use super::{Attributes, Attribute, OwnedName, MissingAttribute, InvalidValue, UnboundPrefix};
+    use super::{Namespace, NamespaceScope, QName};
     use super::{StartDocument, EndDocument, StartElement, EndElement};
-    use super::{parse_str};
+    use super::{StartElementNs, EndElementNs};
+    use super::{Characters, ProcessingInstruction, IgnorableWhitespace};
+    use super::{StripWhitespace, ReportWhitespace, EntityReference, Dtd};
+    use super::{CdataBlock, MergeText, MergeTextAndCdata, Comment};
+    use super::{StartElementEvents, CharacterEvents, WhitespaceEvents, CommentEvents, CdataEvents};
+    use super::{parse_str, parse_bytes, parse_reader, parse_with_handler, ParserConfig, ParseResult};
+    use super::{Latin1, Utf16Be};
+    use super::SaxHandler;
+    use super::error::{ErrorData, ErrorKind, ErrorReport, Warning, Error, Fatal};
+    use super::error::{ParserDomain, TagNameMismatch};
+    use super::{FailFast, CollectErrors, Complete, Cancelled, Failed};
+    use super::error::{SecurityViolation, EntityExpansion, EntityDepth, ExternalEntity};
+    use std::io::MemReader;
 
     fn get_mock_atts() -> Attributes {
         Attributes(Vec::from_slice([
-            Attribute { name: "foo".to_string(), value: "0".to_string() },
-            Attribute { name: "bar".to_string(), value: "1".to_string() },
-            Attribute { name: "baz".to_string(), value: "2".to_string() }
+            Attribute { name: OwnedName("foo".to_string()), value: "0".to_string(), namespace_uri: None },
+            Attribute { name: OwnedName("bar".to_string()), value: "1".to_string(), namespace_uri: None },
+            Attribute { name: OwnedName("baz".to_string()), value: "2".to_string(), namespace_uri: None }
         ]))
     }
 
@@ -203,6 +1209,55 @@ mod tests {
         assert_eq!(atts.find("baz"), Some("2"));
     }
 
+    #[test]
+    fn test_att_iter() {
+        let atts = get_mock_atts();
+        assert_eq!(atts.len(), 3);
+        assert!(atts.contains("bar"));
+        assert!(!atts.contains("qux"));
+        assert_eq!(atts[1].name(), "bar");
+        assert_eq!(atts[1].value(), "1");
+        let names: Vec<&str> = atts.iter().map(|att| att.name()).collect();
+        assert_eq!(names, vec!["foo", "bar", "baz"]);
+        let values: Vec<String> = atts.clone().move_iter().map(|att| att.value).collect();
+        assert_eq!(values, vec!["0".to_string(), "1".to_string(), "2".to_string()]);
+    }
+
+    #[test]
+    fn test_att_require() {
+        let atts = get_mock_atts();
+        assert_eq!(atts.require("baz"), Ok("2"));
+        assert_eq!(atts.require("qux"), Err(MissingAttribute("qux".to_string())));
+    }
+
+    #[test]
+    fn test_att_parse() {
+        let atts = Attributes(Vec::from_slice([
+            Attribute { name: OwnedName("width".to_string()), value: "12".to_string(), namespace_uri: None },
+            Attribute { name: OwnedName("hidden".to_string()), value: " true ".to_string(), namespace_uri: None },
+            Attribute { name: OwnedName("sizes".to_string()), value: " 1 2\n 3".to_string(), namespace_uri: None },
+            Attribute { name: OwnedName("type".to_string()), value: "t:item".to_string(), namespace_uri: None },
+        ]));
+        assert_eq!(atts.parse::<uint>("width"), Ok(12));
+        assert_eq!(atts.parse::<int>("hidden"),
+                   Err(InvalidValue("hidden".to_string(), " true ".to_string())));
+        assert_eq!(atts.parse::<uint>("height"), Err(MissingAttribute("height".to_string())));
+        assert_eq!(atts.parse_or("height", 5u), Ok(5));
+        assert_eq!(atts.parse_bool("hidden"), Ok(true));
+        assert_eq!(atts.parse_list::<uint>("sizes"), Ok(vec![1, 2, 3]));
+
+        let mut scope = NamespaceScope::new();
+        assert_eq!(atts.parse_qname("type", &scope),
+                   Err(UnboundPrefix("type".to_string(), "t:item".to_string())));
+        scope.push([Namespace { prefix: Some("t".to_string()), uri: "urn:t".to_string() }]);
+        scope.push([]);
+        assert_eq!(atts.parse_qname("type", &scope), Ok(QName {
+            local_name: "item".to_string(),
+            prefix: Some("t".to_string()),
+            namespace_uri: Some("urn:t".to_string()),
+        }));
+    }
+
     #[test]
     fn test() {
         let sax = parse_str(
@@ -256,4 +1311,488 @@ mod tests {
         assert_eq!(t.as_slice(),
                    &["hello", "this", "/this", "test:a", "/test:a", "/hello"]);
     }
+
+    #[test]
+    fn test_traversal_elements_ns() {
+        let sax = ParserConfig::new().namespaces(true).parse_str(
+            "<hello xmlns:test=\"http://www.test.org/test\">
+            <this /><test:a test:foo=\"bar\">test</test:a></hello>"
+        );
+        let mut tags = Vec::new();
+        loop {
+            match sax.recv() {
+                Ok(EndDocument) => { break }
+                Ok(StartElementNs(name, atts, namespaces)) => {
+                    if name.local_name.as_slice() == "hello" {
+                        assert_eq!(namespaces.len(), 1);
+                        assert_eq!(namespaces[0].prefix, Some("test".to_string()));
+                        assert_eq!(namespaces[0].uri.as_slice(), "http://www.test.org/test");
+                    }
+                    if name.local_name.as_slice() == "a" {
+                        assert_eq!(name.prefix, Some("test".to_string()));
+                        assert_eq!(name.namespace_uri, Some("http://www.test.org/test".to_string()));
+                        assert_eq!(atts.find("test:foo"), Some("bar"));
+                        assert_eq!(atts.find_ns("http://www.test.org/test", "foo"), Some("bar"));
+                    }
+                    tags.push(name.local_name);
+                }
+                Ok(EndElementNs(name)) => { tags.push(format!("/{:s}", name.local_name)); }
+                _ => { }
+            }
+        }
+        let t: Vec<&str> = tags.iter().map(|t| t.as_slice()).collect();
+        assert_eq!(t.as_slice(),
+                   &["hello", "this", "/this", "a", "/a", "/hello"]);
+    }
+
+    #[test]
+    fn test_bounded_buffer() {
+        let sax = ParserConfig::new().buffer_size(1).parse_str(
+            "<hello><this /><a foo=\"bar\">test</a></hello>"
+        );
+        // the channel is closed once the parse is finished
+        let events: Vec<ParseResult> = sax.iter().collect();
+        assert_eq!(events.len(), 9);
+        assert_eq!(events[0], Ok(StartDocument));
+        assert_eq!(events[8], Ok(EndDocument));
+    }
+
+    #[test]
+    fn test_parse_reader() {
+        // "café" encoded as ISO-8859-1
+        let src = b"<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?><a>caf\xe9</a>";
+        let sax = parse_reader(MemReader::new(Vec::from_slice(src)));
+        let mut text = String::new();
+        for result in sax.iter() {
+            match result {
+                Ok(Characters(chars)) => { text.push_str(chars.as_slice()); }
+                Ok(_) => { }
+                Err(err) => fail!("unexpected error: {}", err),
+            }
+        }
+        assert_eq!(text.as_slice(), "caf\u00e9");
+    }
+
+    #[test]
+    fn test_parse_with_handler() {
+        struct Tags { tags: Vec<String>, text: String }
+
+        impl SaxHandler for Tags {
+            fn start_element(&mut self, name: &str, atts: &Attributes) {
+                self.tags.push(format!("{}{}", name, atts));
+            }
+            fn end_element(&mut self, name: &str) {
+                self.tags.push(format!("/{}", name));
+            }
+            fn characters(&mut self, chars: &str) {
+                self.text.push_str(chars);
+            }
+        }
+
+        let mut handler = Tags { tags: Vec::new(), text: String::new() };
+        parse_with_handler("<hello><this /><a foo=\"bar\">test</a></hello>", &mut handler);
+        let t: Vec<&str> = handler.tags.iter().map(|t| t.as_slice()).collect();
+        assert_eq!(t.as_slice(),
+                   &["hello", "this", "/this", "a foo=\"bar\"", "/a", "/hello"]);
+        assert_eq!(handler.text.as_slice(), "test");
+    }
+
+    #[test]
+    fn test_processing_instruction() {
+        let sax = parse_str(
+            "<?xml-stylesheet href=\"style.css\"?><hello><?tool?></hello>"
+        );
+        let pis: Vec<String> = sax.iter().filter_map(|result| {
+            match result {
+                Ok(event @ ProcessingInstruction(..)) => Some(event.to_string()),
+                _ => None,
+            }
+        }).collect();
+        assert_eq!(pis, vec!["<?xml-stylesheet href=\"style.css\"?>".to_string(),
+                             "<?tool?>".to_string()]);
+    }
+
+    #[test]
+    fn test_whitespace_policy() {
+        let src = "<hello>\n  <a>test</a>\n</hello>";
+
+        let stripped: Vec<ParseResult> = ParserConfig::new()
+            .whitespace(StripWhitespace)
+            .parse_str(src).iter()
+            .filter(|result| match *result { Ok(Characters(_)) => true, _ => false })
+            .collect();
+        assert_eq!(stripped, vec![Ok(Characters("test".to_string()))]);
+
+        let mut whitespace = 0u;
+        for result in ParserConfig::new().whitespace(ReportWhitespace).parse_str(src).iter() {
+            match result {
+                Ok(IgnorableWhitespace(ch)) => {
+                    assert!(ch.as_slice().trim().is_empty());
+                    whitespace += 1;
+                }
+                Ok(Characters(ch)) => assert_eq!(ch.as_slice(), "test"),
+                _ => { }
+            }
+        }
+        assert_eq!(whitespace, 2);
+
+        // text is only blank if the whole run up to the next tag is
+        let src = "<a>  &amp;</a>";
+        for &policy in [StripWhitespace, ReportWhitespace].iter() {
+            let text: Vec<ParseResult> = ParserConfig::new()
+                .whitespace(policy)
+                .parse_str(src).iter()
+                .filter(|result| match *result {
+                    Ok(Characters(_)) | Ok(IgnorableWhitespace(_)) => true,
+                    _ => false,
+                })
+                .collect();
+            assert_eq!(text, vec![Ok(Characters("  &".to_string()))]);
+        }
+    }
+
+    #[test]
+    fn test_entity_reference() {
+        let src = "<!DOCTYPE a [<!ENTITY custom \"value\">]><a>x&custom;y</a>";
+
+        let mut text = String::new();
+        for result in parse_str(src).iter() {
+            match result {
+                Ok(Characters(ch)) => text.push_str(ch.as_slice()),
+                Ok(EntityReference(_)) => fail!("the entity should have been expanded"),
+                _ => { }
+            }
+        }
+        assert_eq!(text.as_slice(), "xvaluey");
+
+        let events: Vec<ParseResult> = ParserConfig::new()
+            .expand_entities(false)
+            .parse_str(src).iter()
+            .filter(|result| match *result {
+                Ok(Characters(_)) | Ok(EntityReference(_)) => true,
+                _ => false,
+            })
+            .collect();
+        assert_eq!(events, vec![Ok(Characters("x".to_string())),
+                                Ok(EntityReference("custom".to_string())),
+                                Ok(Characters("y".to_string()))]);
+    }
+
+    #[test]
+    fn test_coalesce_text() {
+        let text = |config: ParserConfig, src: &str| -> Vec<ParseResult> {
+            config.parse_str(src).iter()
+                .filter(|result| match *result {
+                    Ok(Characters(_)) | Ok(CdataBlock(_)) => true,
+                    _ => false,
+                })
+                .collect()
+        };
+
+        let src = "<!DOCTYPE a [<!ENTITY e \"value\">]><a>x&e;&amp;<![CDATA[<y>]]>z<b/></a>";
+        assert!(text(ParserConfig::new(), src).len() > 3);
+        assert_eq!(text(ParserConfig::new().coalesce_text(MergeText), src),
+                   vec![Ok(Characters("xvalue&".to_string())),
+                        Ok(CdataBlock("<y>".to_string())),
+                        Ok(Characters("z".to_string()))]);
+        assert_eq!(text(ParserConfig::new().coalesce_text(MergeTextAndCdata), src),
+                   vec![Ok(Characters("xvalue&<y>z".to_string()))]);
+
+        // the whitespace policy sees the whole run, however it is split
+        let src = "<a>  &amp;</a>";
+        let config = ParserConfig::new().whitespace(StripWhitespace);
+        assert_eq!(text(config.clone(), src), vec![Ok(Characters("  &".to_string()))]);
+        assert_eq!(text(config.coalesce_text(MergeText), src),
+                   vec![Ok(Characters("  &".to_string()))]);
+    }
+
+    #[test]
+    fn test_event_mask() {
+        let src = "<!DOCTYPE a [<!ELEMENT a ANY>]><?pi?><a>x<!--c--><![CDATA[y]]> <b/></a>";
+        let collect = |config: ParserConfig| -> Vec<ParseResult> {
+            config.parse_str(src).iter().collect()
+        };
+
+        let starts = collect(ParserConfig::new().events(StartElementEvents));
+        assert_eq!(starts.len(), 2);
+        assert!(starts.iter().all(|result| match *result {
+            Ok(StartElement(..)) => true,
+            _ => false,
+        }));
+
+        // a CDATA section is not passed off as character data
+        assert_eq!(collect(ParserConfig::new().events(CharacterEvents | CommentEvents)),
+                   vec![Ok(Characters("x".to_string())),
+                        Ok(Comment("c".to_string())),
+                        Ok(Characters(" ".to_string()))]);
+
+        assert_eq!(collect(ParserConfig::new()
+                               .whitespace(ReportWhitespace)
+                               .events(WhitespaceEvents)),
+                   vec![Ok(IgnorableWhitespace(" ".to_string()))]);
+
+        // the mask applies to text once it has been joined
+        assert_eq!(collect(ParserConfig::new()
+                               .whitespace(ReportWhitespace)
+                               .coalesce_text(MergeText)
+                               .events(WhitespaceEvents)),
+                   vec![Ok(IgnorableWhitespace(" ".to_string()))]);
+        assert_eq!(collect(ParserConfig::new()
+                               .coalesce_text(MergeTextAndCdata)
+                               .events(CdataEvents)),
+                   vec![]);
+
+        // masked markup still ends a run of text
+        let src = "<a>x<b/>y</a>";
+        for config in [ParserConfig::new().whitespace(StripWhitespace),
+                       ParserConfig::new().coalesce_text(MergeText)].iter() {
+            let text: Vec<ParseResult> = config.clone().events(CharacterEvents)
+                .parse_str(src).iter()
+                .collect();
+            assert_eq!(text, vec![Ok(Characters("x".to_string())),
+                                  Ok(Characters("y".to_string()))]);
+        }
+    }
+
+    #[test]
+    fn test_dtd_events() {
+        let sax = parse_str(
+            "<!DOCTYPE book PUBLIC \"-//Test//DTD Book//EN\" \"book.dtd\" [
+                <!ELEMENT book (title, (chapter | appendix)+)>
+                <!ELEMENT title (#PCDATA | em)*>
+                <!ATTLIST book lang (en | fr) \"en\" id ID #REQUIRED>
+                <!ENTITY author \"Anon\">
+                <!NOTATION png SYSTEM \"image/png\">
+            ]>
+            <book id=\"b\"/>"
+        );
+        let decls: Vec<String> = sax.iter().filter_map(|result| {
+            match result {
+                Ok(Dtd(event)) => Some(event.to_string()),
+                _ => None,
+            }
+        }).collect();
+        let d: Vec<&str> = decls.iter().map(|d| d.as_slice()).collect();
+        assert_eq!(d.as_slice(), &[
+            "<!DOCTYPE book PUBLIC \"-//Test//DTD Book//EN\" \"book.dtd\">",
+            "<!ELEMENT book (title,(chapter|appendix)+)>",
+            "<!ELEMENT title (#PCDATA|em)*>",
+            "<!ATTLIST book lang (en|fr) \"en\">",
+            "<!ATTLIST book id ID #REQUIRED>",
+            "<!ENTITY author \"Anon\">",
+            "<!NOTATION png SYSTEM \"image/png\">",
+            "EXTERNAL SUBSET book PUBLIC \"-//Test//DTD Book//EN\" \"book.dtd\"",
+        ]);
+    }
+
+    #[test]
+    fn test_positions() {
+        let src = "<hello>\n  <a foo=\"bar\">test</a>\n</hello>";
+
+        let sax = parse_str(src);
+        for _ in sax.iter() {
+            assert_eq!(sax.position(), None);
+        }
+
+        let sax = ParserConfig::new().positions(true).parse_str(src);
+        loop {
+            match sax.recv() {
+                Ok(StartElement(ref name, _)) if name.as_slice() == "a" => {
+                    let position = sax.position().unwrap();
+                    assert_eq!(position.line, 2);
+                    assert!(position.column > 3);
+                    assert!(position.byte_offset > 8);
+                }
+                Ok(EndDocument) => break,
+                _ => { }
+            }
+        }
+    }
+
+    #[test]
+    fn test_parser_options() {
+        let src = "<!DOCTYPE a [<!ATTLIST a lang CDATA \"en\">]><a><![CDATA[x<y]]></a>";
+
+        let mut cdata = Vec::new();
+        let mut chars = Vec::new();
+        let mut lang = None;
+        let sax = ParserConfig::new()
+            .namespaces(true)
+            .cdata_as_text(true)
+            .default_attributes(true)
+            .parse_str(src);
+        for result in sax.iter() {
+            match result {
+                Ok(CdataBlock(text)) => cdata.push(text),
+                Ok(Characters(text)) => chars.push(text),
+                Ok(StartElementNs(_, atts, _)) => lang = atts.find_clone("lang"),
+                _ => { }
+            }
+        }
+        assert!(cdata.is_empty());
+        assert_eq!(chars, vec!["x<y".to_string()]);
+        assert_eq!(lang, Some("en".to_string()));
+    }
+
+    fn error_kinds(sax: super::Events) -> Vec<ErrorKind> {
+        sax.iter().filter_map(|result| result.err()).map(|err| err.kind().clone()).collect()
+    }
+
+    #[test]
+    fn test_security_limits() {
+        let laughs = "<!DOCTYPE a [
+                <!ENTITY l0 \"lollollollollollollollollollollollollollollollol\">
+                <!ENTITY l1 \"&l0;&l0;&l0;&l0;&l0;&l0;&l0;&l0;&l0;&l0;\">
+                <!ENTITY l2 \"&l1;&l1;&l1;&l1;&l1;&l1;&l1;&l1;&l1;&l1;\">
+                <!ENTITY l3 \"&l2;&l2;&l2;&l2;&l2;&l2;&l2;&l2;&l2;&l2;\">
+                <!ENTITY l4 \"&l3;&l3;&l3;&l3;&l3;&l3;&l3;&l3;&l3;&l3;\">
+                <!ENTITY l5 \"&l4;&l4;&l4;&l4;&l4;&l4;&l4;&l4;&l4;&l4;\">
+            ]>
+            <a>&l5;</a>";
+        assert_eq!(error_kinds(parse_str(laughs)),
+                   vec![SecurityViolation(EntityExpansion)]);
+        assert_eq!(error_kinds(ParserConfig::new().max_entity_depth(4).parse_str(laughs)),
+                   vec![SecurityViolation(EntityDepth("l5".to_string()))]);
+
+        let external = "<!DOCTYPE a [<!ENTITY ext SYSTEM \"file:///etc/passwd\">]><a>&ext;</a>";
+        assert_eq!(error_kinds(parse_str(external)),
+                   vec![SecurityViolation(ExternalEntity("ext".to_string()))]);
+    }
+
+    #[test]
+    fn test_should_stop() {
+        struct FirstElements { names: Vec<String>, ended: bool }
+
+        impl SaxHandler for FirstElements {
+            fn start_element(&mut self, name: &str, _: &Attributes) {
+                self.names.push(name.to_string());
+            }
+            fn end_document(&mut self) {
+                self.ended = true;
+            }
+            fn should_stop(&self) -> bool {
+                self.names.len() == 2
+            }
+        }
+
+        let mut handler = FirstElements { names: Vec::new(), ended: false };
+        let (outcome, _) = parse_with_handler("<a><b/><c/><d/></a>", &mut handler);
+        assert_eq!(outcome, Cancelled);
+        assert_eq!(handler.names, vec!["a".to_string(), "b".to_string()]);
+        assert!(!handler.ended);
+    }
+
+    fn text(sax: super::Events) -> String {
+        let mut text = String::new();
+        for result in sax.iter() {
+            match result {
+                Ok(Characters(chars)) => text.push_str(chars.as_slice()),
+                Ok(_) => { }
+                Err(err) => fail!("unexpected error: {}", err),
+            }
+        }
+        text
+    }
+
+    #[test]
+    fn test_parse_bytes() {
+        // UTF-16LE with a byte order mark
+        let mut utf16le = vec![0xffu8, 0xfe];
+        for unit in "<a>caf\u00e9</a>".utf16_units() {
+            utf16le.push(unit as u8);
+            utf16le.push((unit >> 8) as u8);
+        }
+        assert_eq!(text(parse_bytes(utf16le.as_slice())).as_slice(), "caf\u00e9");
+
+        // UTF-16BE without a byte order mark
+        let mut utf16be = Vec::new();
+        for unit in "<a>caf\u00e9</a>".utf16_units() {
+            utf16be.push((unit >> 8) as u8);
+            utf16be.push(unit as u8);
+        }
+        let sax = ParserConfig::new().encoding(Utf16Be).parse_bytes(utf16be.as_slice());
+        assert_eq!(text(sax).as_slice(), "caf\u00e9");
+
+        // ISO-8859-1 with a declaration, and forced without one
+        let latin1 = b"<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?><a>caf\xe9</a>";
+        assert_eq!(text(parse_bytes(latin1)).as_slice(), "caf\u00e9");
+        let sax = ParserConfig::new().encoding(Latin1).parse_bytes(b"<a>caf\xe9</a>");
+        assert_eq!(text(sax).as_slice(), "caf\u00e9");
+    }
+
+    #[test]
+    fn test_error_data() {
+        let errors: Vec<ErrorData> = parse_str("<a>\n</b>").iter()
+            .filter_map(|result| result.err())
+            .collect();
+        let err = &errors[0];
+        assert_eq!(err.level(), Fatal);
+        assert_eq!(err.line(), 2);
+        assert_eq!(err.domain(), ParserDomain);
+        assert_eq!(err.code(), TagNameMismatch);
+        assert_eq!(err.str1(), Some("a"));
+        assert_eq!(err.str2(), Some("b"));
+        assert_eq!(err.int1(), 1);
+        assert!(err.message().starts_with("Opening and ending tag mismatch"));
+    }
+
+    fn errors_and_report(sax: super::Events) -> (Vec<ErrorData>, ErrorReport) {
+        let errors = sax.iter().filter_map(|result| result.err()).collect();
+        (errors, sax.report().unwrap())
+    }
+
+    #[test]
+    fn test_error_policy() {
+        // an unsupported version is only a warning
+        let src = "<?xml version=\"1.5\"?><a><b></a>";
+
+        let (errors, report) = errors_and_report(parse_str(src));
+        assert_eq!(errors[0].level(), Warning);
+        assert_eq!(report.count(Warning), 1);
+        assert!(report.count(Fatal) >= 1);
+        assert_eq!(errors.len(),
+                   report.count(Warning) + report.count(Error) + report.count(Fatal));
+        assert!(report.errors().is_empty());
+
+        let sax = ParserConfig::new().error_policy(FailFast).parse_str(src);
+        let (errors, report) = errors_and_report(sax);
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[1].level(), Fatal);
+        assert_eq!(report.count(Fatal), 1);
+
+        let sax = ParserConfig::new().error_policy(CollectErrors).parse_str(src);
+        let (errors, report) = errors_and_report(sax);
+        assert!(errors.is_empty());
+        assert_eq!(report.errors()[0].level(), Warning);
+        assert_eq!(report.errors().len(), report.count(Warning) + report.count(Fatal));
+
+        let sax = ParserConfig::new().warnings_as_errors(true).parse_str(src);
+        let (errors, report) = errors_and_report(sax);
+        assert_eq!(errors[0].level(), Error);
+        assert_eq!(report.count(Warning), 0);
+        assert_eq!(report.count(Error), 1);
+
+        struct Nothing;
+        impl SaxHandler for Nothing {}
+        let (outcome, report) = parse_with_handler("<a/>", &mut Nothing);
+        assert_eq!(outcome, Complete);
+        assert!(report.is_empty());
+    }
+
+    #[test]
+    fn test_parse_outcome() {
+        let sax = parse_str("<hello><a foo=\"bar\">test</a></hello>");
+        assert_eq!(sax.outcome(), None);
+        let events: Vec<ParseResult> = sax.iter().collect();
+        assert_eq!(events[events.len() - 1], Ok(EndDocument));
+        assert_eq!(sax.outcome(), Some(Complete));
+
+        // the iterator ends even though `EndDocument` never arrives
+        let sax = parse_str("<hello><a foo=\"bar\">te");
+        for _ in sax.iter() { }
+        match sax.outcome() {
+            Some(Failed(err)) => assert_eq!(err.level(), Fatal),
+            outcome => fail!("unexpected outcome: {}", outcome),
+        }
+    }
 }